@@ -0,0 +1,46 @@
+use std::hint::black_box;
+
+use chrono::{TimeZone, Utc};
+use chrono_systemd_time::{parse_timestamp_tz_at, Parser};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Timestamps with and without whitespace, which allocate a stripped copy only when present.
+const TIMESTAMPS: [&str; 7] = [
+    "now",
+    "2018-08-20",
+    "2018-08-20 09:11:12 +1h2m3s",
+    "2018-08-20 09:11:12 + 1h 2m 3s",
+    "+1h2m3s",
+    "+ 1h 2m 3s",
+    "1h 2m 3s ago",
+];
+
+fn parse_timestamp(c: &mut Criterion) {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+
+    let mut group = c.benchmark_group("parse_timestamp");
+    for timestamp in TIMESTAMPS {
+        group.bench_function(timestamp, |b| {
+            b.iter(|| parse_timestamp_tz_at(black_box(timestamp), Utc, now))
+        });
+    }
+    group.finish();
+}
+
+/// Compare an exact keyword, which takes the fast path, against the same keyword in another case,
+/// which a case insensitive parser only resolves after searching for offsets.
+fn parse_keyword(c: &mut Criterion) {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().case_insensitive(true);
+
+    let mut group = c.benchmark_group("parse_keyword");
+    for timestamp in ["now", "NOW"] {
+        group.bench_function(timestamp, |b| {
+            b.iter(|| parser.parse_tz_at(black_box(timestamp), Utc, now))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_timestamp, parse_keyword);
+criterion_main!(benches);
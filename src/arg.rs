@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use clap::builder::ValueParser;
+
+use crate::{parse_timestamp_utc, Error};
+
+/// Parse a command line argument into a `DateTime` in UTC, for use with `#[arg(value_parser = ...)]`.
+///
+/// The timestamp is parsed as with [`parse_timestamp_utc`](crate::parse_timestamp_utc),
+/// with an ambiguous time rejected by [`Error::Ambiguous`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_utc_arg;
+/// use chrono::{DateTime, TimeZone, Utc};
+/// use clap::{Arg, Command};
+///
+/// let command = Command::new("app").arg(Arg::new("deadline").long("deadline").value_parser(parse_utc_arg));
+/// let matches = command.get_matches_from(["app", "--deadline", "@1529578800"]);
+/// assert_eq!(matches.get_one::<DateTime<Utc>>("deadline"),
+///            Some(&Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap()));
+/// ```
+pub fn parse_utc_arg(arg: &str) -> Result<DateTime<Utc>, Error> {
+    parse_timestamp_utc(arg)?.into_single()
+}
+
+/// A clap `ValueParser` parsing a command line argument into a `DateTime` in UTC, as with [`parse_utc_arg`].
+pub fn value_parser_utc() -> ValueParser {
+    ValueParser::new(parse_utc_arg)
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+
+/// A source of the current time, which relative times such as `"now"` and `"today"` are resolved against.
+///
+/// Environments without an ambient clock, such as `wasm32-unknown-unknown`, may implement this
+/// to supply the time from elsewhere, e.g. from `js_sys::Date`.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_timestamp_tz_with_clock, Clock};
+/// use chrono::{DateTime, TimeZone, Utc};
+///
+/// struct FixedClock(DateTime<Utc>);
+///
+/// impl Clock for FixedClock {
+///     fn now(&self) -> DateTime<Utc> {
+///         self.0
+///     }
+/// }
+///
+/// let clock = FixedClock(Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap());
+/// assert_eq!(parse_timestamp_tz_with_clock("today", Utc, &clock).unwrap().single(),
+///            Some(Utc.with_ymd_and_hms(2018, 6, 21, 0, 0, 0).unwrap()));
+/// ```
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock, as read by [`Utc::now`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
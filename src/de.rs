@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::de::{Deserialize, Deserializer, Error as _};
+
+use crate::{parse_timestamp_utc, LocalDateTime};
+
+/// Deserialize a timestamp string into a `DateTime` in UTC, for use with `#[serde(deserialize_with = "...")]`.
+///
+/// The timestamp is parsed as with [`parse_timestamp_utc`](crate::parse_timestamp_utc).
+///
+/// # Examples
+/// ```rust
+/// use chrono::{DateTime, Utc};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(deserialize_with = "chrono_systemd_time::deserialize_utc")]
+///     deadline: DateTime<Utc>,
+/// }
+///
+/// let config: Config = serde_json::from_str(r#"{ "deadline": "@1529578800" }"#).unwrap();
+/// assert_eq!(config.deadline.timestamp(), 1529578800);
+/// ```
+pub fn deserialize_utc<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp = String::deserialize(deserializer)?;
+    parse_timestamp_utc(&timestamp)
+        .and_then(LocalDateTime::into_single)
+        .map_err(D::Error::custom)
+}
@@ -0,0 +1,26 @@
+/// A `Duration` broken into the time units of a time span, as returned by [`duration_components`].
+///
+/// Each component is the number of its time unit left over once the larger time units are taken out,
+/// using the same weights as parsing: a month is 30.44 days and a year is 365.25 days.
+/// Every component of a negative duration is negative or zero.
+///
+/// [`duration_components`]: crate::duration_components
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DurationComponents {
+    /// The number of years, each of 365.25 days.
+    pub years: i64,
+    /// The number of months, each of 30.44 days.
+    pub months: i64,
+    /// The number of weeks.
+    pub weeks: i64,
+    /// The number of days.
+    pub days: i64,
+    /// The number of hours.
+    pub hours: i64,
+    /// The number of minutes.
+    pub minutes: i64,
+    /// The number of seconds.
+    pub seconds: i64,
+    /// The number of microseconds, truncating any nanoseconds.
+    pub micros: i64,
+}
@@ -1,25 +1,145 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::num::ParseIntError;
+
+use chrono::NaiveDateTime;
+
+use crate::unit_to_nsec;
+
 /// Describes an error during the parsing of a timestamp.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     /// The timestamp is incorrectly formatted.
     Format(String),
-    /// The timestamp contains a component that cannot be parsed into a number, or the number overflowed.
-    Number(String),
+    /// The timestamp contains a component that cannot be parsed into a number.
+    Number {
+        /// A description of the component.
+        msg: String,
+        /// The underlying error, when the number was rejected by [`str::parse`].
+        source: Option<ParseIntError>,
+    },
+    /// The timestamp contains a number, or a sum of numbers, too large to be represented.
+    Overflow(String),
     /// The timestamp contains a component that cannot be parsed into a time unit.
-    TimeUnit(String),
+    ///
+    /// When the component is an uppercase form of a time unit (e.g. `S`), its message notes that time units are
+    /// case-sensitive, unless parsed with [`Parser::case_insensitive`].
+    ///
+    /// [`Parser::case_insensitive`]: crate::Parser::case_insensitive
+    TimeUnit {
+        /// The component which is not a time unit.
+        unit: String,
+        /// The byte position of the component in the timestamp, or in the time span when parsing a time span.
+        at: usize,
+        /// The timestamp, or the time span when parsing a time span, containing the component.
+        input: String,
+    },
     /// The timestamp is invalid in the given timezone.
-    Never,
+    ///
+    /// This is the case for a local time skipped by the timezone, such as a date whose midnight falls in a
+    /// daylight saving time spring-forward, unless the parser snaps such gaps with [`Parser::snap_gaps`].
+    ///
+    /// Contains the local time which does not exist, when known. It is unknown only when converting
+    /// chrono's `LocalResult::None`, which does not carry the local time.
+    ///
+    /// [`Parser::snap_gaps`]: crate::Parser::snap_gaps
+    Never(Option<NaiveDateTime>),
+    /// The timestamp is ambiguous in the given timezone, and a single time was required.
+    ///
+    /// Contains the candidate times.
+    Ambiguous(String),
+    /// A timestamp in a list of timestamps is invalid.
+    List {
+        /// The position of the timestamp in the list, counting from zero.
+        index: usize,
+        /// The error parsing the timestamp.
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Move the position of an error relative to a str to be relative to the str containing it.
+    pub(crate) fn map_position(self, f: impl FnOnce(usize) -> usize) -> Self {
+        match self {
+            Error::TimeUnit { unit, at, input } => Error::TimeUnit {
+                unit,
+                at: f(at),
+                input,
+            },
+            e => e,
+        }
+    }
+
+    /// Attach the full input to an error describing a component of it, so that the error is understood on its own.
+    ///
+    /// Errors about the timezone rather than the input, [`Error::Never`] and [`Error::Ambiguous`], are unchanged.
+    pub(crate) fn with_input(self, input: &str) -> Self {
+        let msg_in = |msg: String| format!("{msg} (in {input:?})");
+        match self {
+            Error::Format(msg) => Error::Format(msg_in(msg)),
+            Error::Number { msg, source } => Error::Number {
+                msg: msg_in(msg),
+                source,
+            },
+            Error::Overflow(msg) => Error::Overflow(msg_in(msg)),
+            Error::TimeUnit { unit, at, .. } => Error::TimeUnit {
+                unit,
+                at,
+                input: input.into(),
+            },
+            e => e,
+        }
+    }
 }
 
-impl std::error::Error for Error {}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Number {
+                source: Some(e), ..
+            } => Some(e),
+            Error::List { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Error::Format(emsg) => write!(f, "invalid timestamp format: {emsg}"),
-            Error::Number(emsg) => write!(f, "invalid timestamp number: {emsg}"),
-            Error::TimeUnit(unit) => write!(f, "invalid time unit: {unit}"),
-            Error::Never => write!(f, "invalid timestamp in the given timezone"),
+            Error::Number { msg, .. } => write!(f, "invalid timestamp number: {msg}"),
+            Error::Overflow(emsg) => write!(f, "timestamp number overflowed: {emsg}"),
+            Error::TimeUnit { unit, at, input } => {
+                write!(f, "invalid time unit at byte {at}: {unit} (in {input:?})")?;
+                // an uppercase form of a time unit is a likely mistake, as time units are case-sensitive
+                let lower = unit.to_lowercase();
+                if lower != *unit && unit_to_nsec(&lower).is_some() {
+                    write!(
+                        f,
+                        "; time units are case-sensitive, so `{unit}` must be written `{lower}` \
+                         (only months are uppercase, as `M`, since `m` is minutes)"
+                    )?;
+                }
+                Ok(())
+            }
+            Error::Never(Some(time)) => write!(
+                f,
+                "invalid timestamp in the given timezone: the local time {time} is skipped by the timezone \
+                 (e.g. a daylight saving time transition)"
+            ),
+            Error::Never(None) => write!(f, "invalid timestamp in the given timezone"),
+            Error::Ambiguous(times) => {
+                write!(f, "ambiguous timestamp in the given timezone: {times}")
+            }
+            Error::List { index, source } => {
+                write!(
+                    f,
+                    "invalid timestamp at index {index} of the list: {source}"
+                )
+            }
         }
     }
 }
@@ -6,24 +6,57 @@
 //! ## Timestamp Format
 //!
 //! The supported timestamp formats are any defined by the systemd.time specifications, with a few exceptions:
-//! * time units **must** accompany all time span values.
-//! * time zone suffixes are **not** supported.
-//! * weekday prefixes are **not** supported.
+//! * time units **must** accompany all time span values, except for a bare number of seconds since the epoch (`"@1529578800"`).
+//! * time zone suffixes are **not** supported, except for `UTC` and `local`, numeric offsets such as `+0200` with `accept_numeric_zone`, and RFC 3339 times parsed with `parse_timestamp_rfc3339_tz`.
 //!
 //! The format of a timestamp may be either a time, a time span, or a combination of a time +/- a time span.
 //! * When only a time is given, the parsed time is returned.
 //! * When only a time span is given, the time span is added or subtracted from the current time (now).
 //! * When a combination of a time and a time span is given, the time span is added or subtracted from the parsed time.
+//! * Several time spans may be chained, each added or subtracted in turn, e.g. `"today + 9h - 30m"`.
+//!
+//! Timestamps are most simply parsed with `parse_timestamp` into the local timezone, or `parse_timestamp_utc` into UTC.
+//! `parse_timestamp_tz` parses into any given timezone. `parse_to_utc` parses in any given timezone into a single instant in UTC,
+//! resolving an ambiguous time to its earliest, as does converting into a `UtcTimestamp` with `TryFrom`. `parse_timestamp_prefix_tz` parses a timestamp at the start of a larger input,
+//! such as a log line, returning the unparsed remainder.
+//! `parse_many_tz` parses a batch of timestamps, collecting the result of each.
+//! `parse_list_tz` parses a list of timestamps separated by `", "`, e.g. `"now, today + 1h, epoch"`.
+//! `parse_range_tz` parses a range of two timestamps separated by `" .. "`, e.g. `"yesterday .. today"`.
+//! `parse_timestamp_tz_meta` also returns whether the time is relative to now, such as to decide whether to cache it.
+//! `parse_time_until_tz` parses a timestamp into the signed duration from now until it, such as for a countdown.
+//! `parse_time_tz` parses only a time, which may be combined with a time span parsed by `parse_duration`.
+//! `parse_time_on_date_tz` parses a timestamp whose time given without a date, such as `"09:11"`, is on a given date.
+//! `is_valid_timestamp` checks whether a timestamp is well formed, such as for form validation.
+//! `tokenize` splits a timestamp into its tokens, such as for syntax highlighting.
+//!
+//! With the `serde` feature, `deserialize_utc` deserializes a timestamp string into a `DateTime<Utc>`,
+//! e.g. `#[serde(deserialize_with = "chrono_systemd_time::deserialize_utc")]`.
+//! `LocalDateTime` is also serialized, as an RFC 3339 string or a sequence of the two strings of an ambiguous time.
+//! With the `clap` feature, `parse_utc_arg` parses a command line argument into a `DateTime<Utc>`,
+//! e.g. `#[arg(value_parser = chrono_systemd_time::parse_utc_arg)]`.
+//! With the `proptest` feature, `timestamp_strategy` generates valid timestamps along with the `DateTime<Utc>` they parse into.
+//!
+//! Without the default `std` feature, the crate is `no_std` and requires only `alloc`. The functions which read the system clock
+//! are then unavailable, leaving `parse_timestamp_tz_at`, `parse_timestamp_tz_with_clock`, `Parser::parse_tz_at`,
+//! `is_valid_timestamp`, `parse_duration`, `parse_offset_signed`, `parse_std_duration`, `format_duration`,
+//! `parse_duration_in_place`, `add_calendar_span`, `tokenize`, `duration_components`, `time_units` and `time_keywords`.
+//!
+//! Relative times such as `"now"` and `"today"` are resolved against the system clock, or against a given instant with `parse_timestamp_tz_at`,
+//! or against the time read from a `Clock` with `parse_timestamp_tz_with_clock`.
+//!
+//! The parsing functions use the default options of a [`Parser`], which may instead be configured,
+//! for example to apply months on the calendar or to accept times on a twelve-hour clock.
 //!
 //! Examples of parsing valid timestamps, assuming now is 2018-06-21 01:02:03:
 //! ```rust,ignore
-//!     parse_timestamp_tz("2018-08-20 09:11:12.123", Utc) == "2018-08-20T09:11:12.000123Z"
+//!     parse_timestamp_tz("2018-08-20 09:11:12.123", Utc) == "2018-08-20T09:11:12.123Z"
 //!     parse_timestamp_tz("2018-08-20 09:11:12", Utc) == "2018-08-20T09:11:12Z"
 //!     parse_timestamp_tz("18-08-20 09:11:12 +2m", Utc) == "2018-08-20T09:13:12Z"
 //!     parse_timestamp_tz("2018-08-20 + 1h2m3s", Utc) == "2018-08-20T01:02:03Z"
 //!     parse_timestamp_tz("18-08-20 - 1h 2m 3s", Utc) == "2018-08-19T22:57:57Z"
+//!     parse_timestamp_tz("18-08-20 - 1h + 30m", Utc) == "2018-08-19T23:30:00Z"
 //!     parse_timestamp_tz("09:11:12 -1day", Utc) == "2018-06-20T09:11:12Z"
-//!     parse_timestamp_tz("09:11:12.123", Utc) == "2018-06-21T09:11:12.000123Z"
+//!     parse_timestamp_tz("09:11:12.123", Utc) == "2018-06-21T09:11:12.123Z"
 //!     parse_timestamp_tz("11:12", Utc) == "2018-06-21T11:12:00Z"
 //!     parse_timestamp_tz("now", Utc) == "2018-06-21T01:02:03.203918151Z"
 //!     parse_timestamp_tz("today", Utc) == "2018-06-21T00:00:00Z"
@@ -32,8 +65,12 @@
 //!
 //!     parse_timestamp_tz("epoch +1529578800s", Utc) == "2018-06-21T11:00:00Z"
 //!     parse_timestamp_tz("@1529578800s", Utc) == "2018-06-21T11:00:00Z"
+//!     parse_timestamp_tz("@1529578800", Utc) == "2018-06-21T11:00:00Z"
+//!     parse_timestamp_tz("@1529578800.5", Utc) == "2018-06-21T11:00:00.5Z"
 //!     parse_timestamp_tz("now +4h50m", Utc) == "2018-06-21T05:52:03.203918151Z"
 //!     parse_timestamp_tz("4h50m left", Utc) == "2018-06-21T05:52:03.203918151Z"
+//!     parse_timestamp_tz("in 4h50m", Utc) == "2018-06-21T05:52:03.203918151Z"
+//!     parse_timestamp_tz("4h50m from now", Utc) == "2018-06-21T05:52:03.203918151Z"
 //!     parse_timestamp_tz("+4h50m", Utc) == "2018-06-21T05:52:03.203918151Z"
 //!     parse_timestamp_tz("now -3s", Utc) == "2018-06-21T01:02:00.203918151Z"
 //!     parse_timestamp_tz("3s ago", Utc) == "2018-06-21T01:02:00.203918151Z"
@@ -43,37 +80,61 @@
 //! #### Time
 //! The syntax of a time consists of a set of keywords and strftime formats:
 //! * `"now"`, `"epoch"`
+//! * `"infinity"`, `"-infinity"` (the latest and earliest times representable in the timezone, which offsets do not move)
 //! * `"today"`, `"yesterday"`, `"tomorrow"`
+//! * `"midnight"`, `"noon"` (00:00:00 and 12:00:00 today)
 //! * `"%y-%m-%d %H:%M:%S"`, `"%Y-%m-%d %H:%M:%S"`
 //! * `"%y-%m-%d %H:%M"`, `"%Y-%m-%d %H:%M"`
 //! * `"%y-%m-%d"`, `"%Y-%m-%d"`
 //! * `"%H:%M:%S"`
 //! * `"%H:%M"`
 //!
-//! Strftime timestamps with a seconds component may also include a microsecond component, separated by a `'.'`.
+//! A time may be prefixed with a weekday, either abbreviated or in full and in any case (e.g. `"Mon 2018-08-20"`).
+//! The weekday must match that of the time, otherwise the timestamp is rejected.
+//!
+//! A time may be suffixed with `UTC` or `local` (e.g. `"2018-08-20 09:11:12 UTC"`), in which case the time is interpreted
+//! in that timezone, rather than the given timezone, before being converted into the given timezone.
+//!
+//! Strftime timestamps with a seconds component may also include a fractional seconds component, separated by a `'.'`
+//! or a `','` (e.g. `"2018-08-20 09:11:12,123"`).
+//! The separator must directly join the two components, so whitespace on either side of it (e.g. `"09:11:12 .5"`)
+//! is rejected.
+//! A seconds component of 60 is a leap second (e.g. `"2016-12-31 23:59:60"`), represented as chrono represents leap seconds.
+//! As with chrono, offsets from a leap second disregard it, so `"2016-12-31 23:59:60 + 1s"` is `"2017-01-01 00:00:00"`.
 //! * When the date is omitted, today is assumed.
-//! * When the time is omitted, 00:00:00 is assumed.
+//! * When the time is omitted, 00:00:00 is assumed, or the `default_time` of a `Parser`.
+//!   This also applies to the `"today"`, `"yesterday"` and `"tomorrow"` keywords.
+//!
+//! The fractional seconds component is read positionally, so `.5` is half a second and `.123` is 123 milliseconds.
+//! As with chrono's `%.f`, digits beyond nanosecond precision are truncated.
+//! Releases up to 0.3 instead read the digits as a count of microseconds (`.123` was 123 microseconds).
 //!
 //! Examples of valid times (assuming now is 2018-06-21 01:02:03):
 //! ```rust,ignore
-//!     "2018-08-20 09:11:12.123" == "2018-08-20T09:11:12.000123"
+//!     "2018-08-20 09:11:12.123" == "2018-08-20T09:11:12.123"
 //!         "2018-08-20 09:11:12" == "2018-08-20T09:11:12"
 //!           "18-08-20 09:11:12" == "2018-08-20T09:11:12"
 //!                  "2018-08-20" == "2018-08-20T00:00:00"
+//!              "Mon 2018-08-20" == "2018-08-20T00:00:00"
 //!                    "18-08-20" == "2018-08-20T00:00:00"
+//!                    "20180820" == "2018-08-20T00:00:00"
 //!                    "09:11:12" == "2018-06-21T09:11:12"
-//!                "09:11:12.123" == "2018-06-21T09:11:12.000123"
+//!                "09:11:12.123" == "2018-06-21T09:11:12.123"
 //!                       "11:12" == "2018-06-21T11:12:00"
 //!                         "now" == "2018-06-21T01:02:03.203918151"
 //!                       "epoch" == "1970-01-01T00:00:00"
+//!                    "infinity" == "+262142-12-31T23:59:59.999999999"
 //!                       "today" == "2018-06-21T00:00:00"
 //!                   "yesterday" == "2018-06-20T00:00:00"
 //!                    "tomorrow" == "2018-06-22T00:00:00"
+//!                    "midnight" == "2018-06-21T00:00:00"
+//!                        "noon" == "2018-06-21T12:00:00"
 //! ```
 //!
 //! #### Time span
 //! A time span is made up of a combination of time units, with the following time units understood:
-//! * `"usec"`, `"us"`, `"µs"`
+//! * `"nanoseconds"`, `"nanosecond"`, `"nsec"`, `"ns"`
+//! * `"usec"`, `"us"`, `"µs"`, `"μs"`
 //! * `"msec"`, `"ms"`
 //! * `"seconds"`, `"second"`, `"sec"`, `"s"`
 //! * `"minutes"`, `"minute"`, `"min"`, `"m"`
@@ -83,7 +144,17 @@
 //! * `"months"`, `"month"`, `"M"` (defined as 30.44 days)
 //! * `"years"`, `"year"`, `"y"` (defined as 365.25 days)
 //!
-//! All components of a time span are added to together.
+//! All components of a time span are added together, except for a component after the first prefixed with a `'-'`,
+//! which is subtracted (e.g. `"3h-30m"`).
+//! The value of a component may include a fractional part, separated by a `'.'`.
+//! A time span may also be parsed on its own with `parse_duration`, or without allocating with `parse_duration_in_place`,
+//! or along with its sign as an offset relative to now
+//! (e.g. `"-3s"` or `"3s ago"`) with `parse_offset_signed`.
+//! `add_calendar_span` adds a time span to any time, applying months and years on the calendar.
+//! `duration_components` breaks a `Duration` into the number of each time unit, such as to display it.
+//! An ISO 8601 duration, such as `"PT1H30M"` or `"P1DT2H"`, is also understood as a time span.
+//! A time span of a greater magnitude than `MAX_SPAN`, about 292,271 years, is rejected.
+//! The time units and time keywords understood are listed by `time_units` and `time_keywords`.
 //!
 //! Examples of valid time spans:
 //! ```rust,ignore
@@ -93,82 +164,169 @@
 //!           "30m22s" == Duration::minutes(30) + Duration::seconds(22)
 //!        "10m 2s 5m" == Duration::minutes(15) + Duration::seconds(2)
 //!         "10d 2 5m" == Duration::days(10) + Duration::minutes(25)
+//!             "1.5h" == Duration::minutes(90)
+//!           "3h-30m" == Duration::minutes(150)
+//!          "PT1H30M" == Duration::minutes(90)
+//!           "P1DT2H" == Duration::days(1) + Duration::hours(2)
 //! ```
 
-#[cfg(test)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(test, feature = "std"))]
 mod tests;
 
+#[cfg(feature = "clap")]
+mod arg;
+mod clock;
+#[cfg(feature = "serde")]
+mod de;
+mod duration_components;
 mod error;
 mod local_datetime;
+mod parser;
+#[cfg(feature = "proptest")]
+mod strategy;
+#[cfg(feature = "std")]
+mod systemd_timestamp;
+mod token;
+
+pub use self::{
+    clock::Clock,
+    duration_components::DurationComponents,
+    error::Error,
+    local_datetime::LocalDateTime,
+    parser::{AmbiguousStrategy, DateOrder, Parser, Resolution, TimeUnit},
+    token::Token,
+};
 
-pub use self::{error::Error, local_datetime::LocalDateTime};
+#[cfg(feature = "std")]
+pub use self::{
+    clock::SystemClock,
+    systemd_timestamp::{SystemdTimestamp, UtcTimestamp},
+};
 
-use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::str;
+#[cfg(feature = "clap")]
+pub use self::arg::{parse_utc_arg, value_parser_utc};
+#[cfg(feature = "serde")]
+pub use self::de::deserialize_utc;
+#[cfg(feature = "proptest")]
+pub use self::strategy::timestamp_strategy;
 
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::str;
+
+#[cfg(feature = "std")]
+use chrono::offset::Local;
 use chrono::offset::Utc;
-use chrono::{Days, Duration};
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
-use once_cell::sync::Lazy;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone};
 
 /*
- * Chrono stores its DateTimes and Durations in i64s, so use that here.
- * Ideally we would use a larger primitive type (and unsigned).
+ * Time units are weighted in nanoseconds, which comfortably fits a year in an i64.
+ * Time spans are accumulated in an i128 of nanoseconds, so intermediate sums do not overflow,
+ * and only the total is limited to what fits in an i64 of microseconds, as published by `MAX_SPAN`.
  */
 
-const USEC_PER_USEC: i64 = 1;
-const USEC_PER_MSEC: i64 = 1_000 * USEC_PER_USEC;
-const USEC_PER_SEC: i64 = 1_000 * USEC_PER_MSEC;
-const USEC_PER_MINUTE: i64 = 60 * USEC_PER_SEC;
-const USEC_PER_HOUR: i64 = 60 * USEC_PER_MINUTE;
-const USEC_PER_DAY: i64 = 24 * USEC_PER_HOUR;
-const USEC_PER_WEEK: i64 = 7 * USEC_PER_DAY;
-const USEC_PER_MONTH: i64 = 2_629_800 * USEC_PER_SEC;
-const USEC_PER_YEAR: i64 = 31_557_600 * USEC_PER_SEC;
+const NSEC_PER_NSEC: i64 = 1;
+const NSEC_PER_USEC: i64 = 1_000 * NSEC_PER_NSEC;
+const NSEC_PER_MSEC: i64 = 1_000 * NSEC_PER_USEC;
+const NSEC_PER_SEC: i64 = 1_000 * NSEC_PER_MSEC;
+const NSEC_PER_MINUTE: i64 = 60 * NSEC_PER_SEC;
+const NSEC_PER_HOUR: i64 = 60 * NSEC_PER_MINUTE;
+const NSEC_PER_DAY: i64 = 24 * NSEC_PER_HOUR;
+const NSEC_PER_WEEK: i64 = 7 * NSEC_PER_DAY;
+const NSEC_PER_MONTH: i64 = 2_629_800 * NSEC_PER_SEC;
+const NSEC_PER_YEAR: i64 = 31_557_600 * NSEC_PER_SEC;
 
-#[rustfmt::skip]
-static USEC_MULTIPLIER: Lazy<HashMap<&'static str, i64>> = Lazy::new(|| {
-    HashMap::from_iter([
-        ("us", USEC_PER_USEC),
-        ("usec", USEC_PER_USEC),
-        ("µs", USEC_PER_USEC),
-
-        ("ms", USEC_PER_MSEC),
-        ("msec", USEC_PER_MSEC),
-
-        ("s", USEC_PER_SEC),
-        ("sec", USEC_PER_SEC),
-        ("second", USEC_PER_SEC),
-        ("seconds", USEC_PER_SEC),
-
-        ("m", USEC_PER_MINUTE),
-        ("min", USEC_PER_MINUTE),
-        ("minute", USEC_PER_MINUTE),
-        ("minutes", USEC_PER_MINUTE),
-
-        ("h", USEC_PER_HOUR),
-        ("hour", USEC_PER_HOUR),
-        ("hours", USEC_PER_HOUR),
-        ("hr", USEC_PER_HOUR),
-
-        ("d", USEC_PER_DAY),
-        ("day", USEC_PER_DAY),
-        ("days", USEC_PER_DAY),
-
-        ("M", USEC_PER_MONTH),
-        ("month", USEC_PER_MONTH),
-        ("months", USEC_PER_MONTH),
-
-        ("w", USEC_PER_WEEK),
-        ("week", USEC_PER_WEEK),
-        ("weeks", USEC_PER_WEEK),
-
-        ("y", USEC_PER_YEAR),
-        ("year", USEC_PER_YEAR),
-        ("years", USEC_PER_YEAR),
-    ])
-});
+/// The largest magnitude of a time span, `i64::MAX` microseconds (about 292,271 years).
+///
+/// A time span, or the total of a timestamp's offsets, of a greater magnitude is rejected with [`Error::Overflow`],
+/// whether positive or negative. Calendar months are not counted towards it.
+/// Adding a far smaller time span to a time may still overflow the range of a `DateTime`.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_duration, Error, MAX_SPAN};
+/// assert_eq!(parse_duration("9223372036854775807us").unwrap(), MAX_SPAN);
+/// assert!(matches!(parse_duration("9223372036854775807us 1ns"), Err(Error::Overflow(_))));
+/// ```
+pub const MAX_SPAN: Duration = Duration::microseconds(i64::MAX);
+
+/// `MAX_SPAN` in nanoseconds.
+const MAX_SPAN_NSECS: i128 = i64::MAX as i128 * NSEC_PER_USEC as i128;
+
+/// Define the list of time units and the lookup of their weights from a single table,
+/// so that a time unit cannot be listed without being understood, or understood without being listed.
+macro_rules! time_units {
+    ($($nsecs:expr => [$($unit:literal),+],)+) => {
+        /// The time units understood in a time span, along with their nanosecond weights, as listed by [`time_units`].
+        const TIME_UNITS: &[(&str, i64)] = &[$($(($unit, $nsecs),)+)+];
+
+        /// The nanosecond weight of a time unit, or `None` when the time unit is not understood.
+        fn unit_to_nsec(unit: &str) -> Option<i64> {
+            let nsecs = match unit {
+                $($($unit)|+ => $nsecs,)+
+                _ => return None,
+            };
+            Some(nsecs)
+        }
+    };
+}
+
+time_units! {
+    NSEC_PER_NSEC => ["ns", "nsec", "nanosecond", "nanoseconds"],
+    // the micro sign (U+00B5) or the greek small letter mu (U+03BC), which are often used interchangeably
+    NSEC_PER_USEC => ["us", "usec", "µs", "μs"],
+    NSEC_PER_MSEC => ["ms", "msec"],
+    NSEC_PER_SEC => ["s", "sec", "second", "seconds"],
+    NSEC_PER_MINUTE => ["m", "min", "minute", "minutes"],
+    NSEC_PER_HOUR => ["h", "hr", "hour", "hours"],
+    NSEC_PER_DAY => ["d", "day", "days"],
+    NSEC_PER_WEEK => ["w", "week", "weeks"],
+    NSEC_PER_MONTH => ["M", "month", "months"],
+    NSEC_PER_YEAR => ["y", "year", "years"],
+}
+
+/// Parse a timestamp returning a `DateTime` in the local timezone.
+///
+/// This is the recommended entry point for simple use, along with [`parse_timestamp_utc`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp;
+/// use chrono::Duration;
+///
+/// let today = parse_timestamp("today").unwrap().earliest();
+/// assert_eq!(parse_timestamp("today + 2h").unwrap().earliest(), today + Duration::hours(2));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp<S: AsRef<str>>(timestamp: S) -> Result<LocalDateTime<Local>, Error> {
+    parse_timestamp_tz(timestamp, Local)
+}
+
+/// Parse a timestamp returning a `DateTime` in UTC.
+///
+/// UTC has no ambiguous times, so the result is always [`LocalDateTime::Single`].
+/// This is the recommended entry point for simple use, along with [`parse_timestamp`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp_utc;
+/// use chrono::{TimeZone, Utc};
+///
+/// assert_eq!(parse_timestamp_utc("@1529578800s").unwrap().single(),
+///            Some(Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap()));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp_utc<S: AsRef<str>>(timestamp: S) -> Result<LocalDateTime<Utc>, Error> {
+    parse_timestamp_tz(timestamp, Utc)
+}
 
 /// Parse a timestamp returning a `DateTime` with the specified timezone.
 ///
@@ -191,196 +349,870 @@ static USEC_MULTIPLIER: Lazy<HashMap<&'static str, i64>> = Lazy::new(|| {
 /// assert_eq!(parse_timestamp_tz_aux("2018-06-21", Utc),
 ///             parse_timestamp_tz_aux("18-06-21 1:00 - 1h", Utc));
 /// ```
+///
+/// The timestamp may be any string type, such as a `&str`, `String`, `&String` or `Box<str>`, and the timezone
+/// may be passed by value or by reference. A reference is borrowed as either the timezone or the reference itself,
+/// so its timezone must be named when the result does not already name it.
+/// ```rust
+/// # use chrono_systemd_time::{parse_timestamp_tz, Error, LocalDateTime};
+/// use chrono::{Local, Utc};
+///
+/// let owned = String::from("2018-08-20 09:11:12");
+/// let boxed: Box<str> = owned.clone().into_boxed_str();
+/// let expected = parse_timestamp_tz("2018-08-20 09:11:12", Utc).unwrap();
+/// assert_eq!(parse_timestamp_tz(&owned, Utc).unwrap(), expected);
+/// assert_eq!(parse_timestamp_tz(&boxed, Utc).unwrap(), expected);
+/// assert_eq!(parse_timestamp_tz(boxed, Utc).unwrap(), expected);
+/// assert_eq!(parse_timestamp_tz(owned, Utc).unwrap(), expected);
+///
+/// let local = Local;
+/// let by_value = parse_timestamp_tz("2018-08-20", local);
+/// let by_reference: Result<LocalDateTime<Local>, Error> = parse_timestamp_tz("2018-08-20", &local);
+/// assert_eq!(by_value, by_reference);
+/// ```
+#[cfg(feature = "std")]
 pub fn parse_timestamp_tz<S, T, Tz>(timestamp: S, timezone: T) -> Result<LocalDateTime<Tz>, Error>
 where
     S: AsRef<str>,
     T: Borrow<Tz>,
     Tz: TimeZone,
 {
-    let tz = timezone.borrow();
+    Parser::new().parse_tz(timestamp, timezone)
+}
+
+/// Parse a timestamp returning a `DateTime` with the specified timezone,
+/// along with whether the time is relative to now.
+///
+/// A relative time, such as that of `"now + 1h"` or `"today"`, is only valid at the instant it was parsed,
+/// whereas an absolute time, such as that of `"2018-08-20"` or `"@1529578800"`, may be cached.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_timestamp_tz_meta, Resolution};
+/// use chrono::Utc;
+///
+/// let (_, resolution) = parse_timestamp_tz_meta("2018-08-20 + 1h", Utc).unwrap();
+/// assert_eq!(resolution, Resolution::Absolute);
+/// let (_, resolution) = parse_timestamp_tz_meta("1h ago", Utc).unwrap();
+/// assert_eq!(resolution, Resolution::RelativeToNow);
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp_tz_meta<S, T, Tz>(
+    timestamp: S,
+    timezone: T,
+) -> Result<(LocalDateTime<Tz>, Resolution), Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_meta_tz_at(timestamp, timezone, Utc::now())
+}
+
+/// Parse a timestamp in the specified timezone returning a single instant in UTC.
+///
+/// A time which is ambiguous in the timezone is resolved to its earliest possible conversion time.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_to_utc;
+/// use chrono::{FixedOffset, TimeZone, Utc};
+///
+/// let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+/// assert_eq!(parse_to_utc("2018-08-20 09:11:12", tz).unwrap(),
+///            Utc.with_ymd_and_hms(2018, 8, 20, 7, 11, 12).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_to_utc<S, T, Tz>(timestamp: S, timezone: T) -> Result<DateTime<Utc>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Ok(parse_timestamp_tz(timestamp, timezone)?
+        .earliest()
+        .with_timezone(&Utc))
+}
+
+/// Parse a timestamp with the specified timezone returning the signed duration from now until it,
+/// which is negative for a timestamp in the past.
+///
+/// The clock is sampled once, for both resolving relative times and measuring the duration.
+/// A time which is ambiguous in the timezone is resolved to its earliest possible conversion time,
+/// while [`Parser::parse_until_tz`] may instead reject it.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_time_until_tz;
+/// use chrono::{Duration, Utc};
+///
+/// assert_eq!(parse_time_until_tz("now + 2h", Utc).unwrap(), Duration::hours(2));
+/// assert_eq!(parse_time_until_tz("3s ago", Utc).unwrap(), Duration::seconds(-3));
+/// assert!(parse_time_until_tz("epoch", Utc).unwrap() < Duration::zero());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_time_until_tz<S, T, Tz>(timestamp: S, timezone: T) -> Result<Duration, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_until_tz(timestamp, timezone)
+}
+
+/// Parse a timestamp returning a `DateTime` with the specified timezone,
+/// resolving relative times against the given `now` instead of the system clock.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp_tz_at;
+/// use chrono::{TimeZone, Utc};
+///
+/// let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+/// let parse = |timestamp| {
+///     parse_timestamp_tz_at(timestamp, Utc, now)
+///         .unwrap()
+///         .single()
+///         .unwrap()
+/// };
+///
+/// assert_eq!(parse("now"), now);
+/// assert_eq!(parse("yesterday -2days"), Utc.with_ymd_and_hms(2018, 6, 18, 0, 0, 0).unwrap());
+/// assert_eq!(parse("4h50m left"), Utc.with_ymd_and_hms(2018, 6, 21, 5, 52, 3).unwrap());
+/// assert_eq!(parse("11:12"), Utc.with_ymd_and_hms(2018, 6, 21, 11, 12, 0).unwrap());
+/// ```
+pub fn parse_timestamp_tz_at<S, T, Tz>(
+    timestamp: S,
+    timezone: T,
+    now: DateTime<Utc>,
+) -> Result<LocalDateTime<Tz>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_tz_at(timestamp, timezone, now)
+}
+
+/// Parse a timestamp returning a `DateTime` with the specified timezone,
+/// resolving relative times against the time read from the given [`Clock`].
+///
+/// This suits environments without a system clock, see [`Clock`] for an example.
+pub fn parse_timestamp_tz_with_clock<S, T, Tz, C>(
+    timestamp: S,
+    timezone: T,
+    clock: &C,
+) -> Result<LocalDateTime<Tz>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+    C: Clock + ?Sized,
+{
+    parse_timestamp_tz_at(timestamp, timezone, clock.now())
+}
+
+/// Parse a timestamp returning a `DateTime` with the specified timezone,
+/// additionally accepting [RFC 3339] times which carry their own offset.
+///
+/// An RFC 3339 time is interpreted in its own offset before being converted into the specified timezone,
+/// so it is never ambiguous. All other timestamps are parsed as with [`parse_timestamp_tz`].
+///
+/// [RFC 3339]: https://www.rfc-editor.org/rfc/rfc3339
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp_rfc3339_tz;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let dt = Utc.with_ymd_and_hms(2018, 8, 20, 7, 11, 12).unwrap();
+/// assert_eq!(parse_timestamp_rfc3339_tz("2018-08-20T09:11:12+02:00", Utc).unwrap().single(), Some(dt));
+/// assert_eq!(parse_timestamp_rfc3339_tz("2018-08-20T07:11:12Z", Utc).unwrap().single(), Some(dt));
+/// assert_eq!(parse_timestamp_rfc3339_tz("2018-08-20T07:11:12Z + 1h", Utc).unwrap().single(),
+///            Some(dt + Duration::hours(1)));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp_rfc3339_tz<S, T, Tz>(
+    timestamp: S,
+    timezone: T,
+) -> Result<LocalDateTime<Tz>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new()
+        .accept_rfc3339(true)
+        .parse_tz(timestamp, timezone)
+}
+
+/// Parse a timestamp from bytes returning a `DateTime` with the specified timezone.
+///
+/// The bytes must be UTF-8, though only the `"µs"` and `"μs"` time units are not ASCII.
+/// The whole input is validated up front, before any of it is parsed,
+/// so bytes which are not UTF-8 anywhere in it are rejected with [`Error::Format`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp_tz_bytes;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let epoch = Utc.timestamp_opt(0, 0).unwrap();
+/// assert_eq!(parse_timestamp_tz_bytes(b"epoch + 5s", Utc).unwrap().single(),
+///            Some(epoch + Duration::seconds(5)));
+/// assert!(parse_timestamp_tz_bytes(b"epoch + 5\xffs", Utc).is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp_tz_bytes<T, Tz>(
+    bytes: &[u8],
+    timezone: T,
+) -> Result<LocalDateTime<Tz>, Error>
+where
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    let timestamp = str::from_utf8(bytes).map_err(|e| {
+        Error::Format(format!(
+            "Timestamp is not valid UTF-8 after byte {}",
+            e.valid_up_to()
+        ))
+    })?;
+    parse_timestamp_tz(timestamp, timezone)
+}
+
+/// Parse each of a batch of timestamps returning a `DateTime` with the specified timezone,
+/// collecting the result of each so that one invalid timestamp does not prevent parsing the rest.
+///
+/// The clock is sampled once, so relative times such as `"now"` agree across the batch.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_many_tz;
+/// use chrono::Utc;
+///
+/// let results = parse_many_tz(["today", "bad", "today + 1h"], Utc);
+/// assert!(results[0].is_ok());
+/// assert!(results[1].is_err());
+/// assert!(results[2].is_ok());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_many_tz<I, S, Tz>(items: I, timezone: Tz) -> Vec<Result<LocalDateTime<Tz>, Error>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    Tz: TimeZone,
+{
+    parse_many_iter_tz(items, timezone).collect()
+}
+
+/// Lazily parse each of a batch of timestamps returning a `DateTime` with the specified timezone,
+/// as with [`parse_many_tz`].
+///
+/// The clock is sampled once when called, so relative times such as `"now"` agree across the batch.
+#[cfg(feature = "std")]
+pub fn parse_many_iter_tz<I, S, Tz>(
+    items: I,
+    timezone: Tz,
+) -> impl Iterator<Item = Result<LocalDateTime<Tz>, Error>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    Tz: TimeZone,
+{
+    let parser = Parser::new();
+    let now = Utc::now();
+    items
+        .into_iter()
+        .map(move |item| parser.parse_tz_at(item, &timezone, now))
+}
+
+/// Parse the timestamp at the start of the input returning a `DateTime` with the specified timezone,
+/// along with the unparsed remainder of the input.
+///
+/// The longest valid timestamp is parsed, which must end at the end of the input or before whitespace.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_timestamp_prefix_tz;
+/// use chrono::{TimeZone, Utc};
+///
+/// let (time, rest) = parse_timestamp_prefix_tz("2018-08-20 09:11:12 some message", Utc).unwrap();
+/// assert_eq!(time.single(), Some(Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap()));
+/// assert_eq!(rest, " some message");
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_timestamp_prefix_tz<T, Tz>(
+    input: &str,
+    timezone: T,
+) -> Result<(LocalDateTime<Tz>, &str), Error>
+where
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_prefix_tz(input, timezone)
+}
+
+/// Parse a list of timestamps separated by `", "` returning each as a `DateTime` with the specified timezone.
+///
+/// Each timestamp is parsed as with [`parse_timestamp_tz`], with the clock sampled once for the whole list.
+/// A comma not followed by a space, such as that of fractional seconds, does not separate timestamps.
+/// The first invalid timestamp is rejected with [`Error::List`], holding its index and error.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_list_tz, Error};
+/// use chrono::{TimeZone, Utc};
+///
+/// let times = parse_list_tz("2018-08-20 09:11:12,5, today + 1h, epoch", Utc).unwrap();
+/// assert_eq!(times.len(), 3);
+/// assert_eq!(times[2].single(), Some(Utc.timestamp_opt(0, 0).unwrap()));
+///
+/// assert!(matches!(parse_list_tz("now, bad, epoch", Utc), Err(Error::List { index: 1, .. })));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_list_tz<S, T, Tz>(list: S, timezone: T) -> Result<Vec<LocalDateTime<Tz>>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_list_tz(list, timezone)
+}
+
+/// Parse a range of two timestamps separated by `" .. "` returning the start and end of the range,
+/// each a `DateTime` with the specified timezone.
+///
+/// Each end of the range is parsed as with [`parse_timestamp_tz`], with the clock sampled once for both.
+/// A range whose start is after its end is rejected with [`Error::Format`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_range_tz;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let (start, end) = parse_range_tz("2018-08-09 .. 2018-08-10 + 1h", Utc).unwrap();
+/// assert_eq!(start.single(), Some(Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()));
+/// assert_eq!(end.single(), Some(Utc.with_ymd_and_hms(2018, 8, 10, 1, 0, 0).unwrap()));
+///
+/// let (start, end) = parse_range_tz("yesterday .. today", Utc).unwrap();
+/// assert_eq!(end.earliest() - start.earliest(), Duration::days(1));
+/// assert!(parse_range_tz("today .. yesterday", Utc).is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_range_tz<S, T, Tz>(
+    range: S,
+    timezone: T,
+) -> Result<(LocalDateTime<Tz>, LocalDateTime<Tz>), Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new().parse_range_tz(range, timezone)
+}
+
+/// Parse only the time of a timestamp, without any offsets, returning a `DateTime` with the specified timezone.
+///
+/// The time may be any keyword or strftime time accepted by [`parse_timestamp_tz`].
+/// Together with [`parse_duration`], this allows composing the time and offset of a timestamp separately.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_duration, parse_time_tz};
+/// use chrono::{TimeZone, Utc};
+///
+/// let time = parse_time_tz("2018-08-20 09:11:12", Utc).unwrap().single().unwrap();
+/// assert_eq!(time, Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap());
+/// assert_eq!(time - parse_duration("1h").unwrap(), Utc.with_ymd_and_hms(2018, 8, 20, 8, 11, 12).unwrap());
+/// assert!(parse_time_tz("2018-08-20 09:11:12 - 1h", Utc).is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_time_tz<S, T, Tz>(timestamp: S, timezone: T) -> Result<LocalDateTime<Tz>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
     let ts = timestamp.as_ref();
-    let ts_nw = ts
-        .chars()
-        .filter(|&c| !c.is_whitespace())
-        .collect::<String>();
+    Parser::new()
+        .parse_time(ts, timezone.borrow(), &Utc::now())
+        .map_err(|e| e.with_input(ts))
+}
 
-    if ts_nw.is_empty() {
-        return Err(Error::Format("Timestamp cannot be empty".to_owned()));
+/// Parse a timestamp returning a `DateTime` with the specified timezone,
+/// where a time given without a date (e.g. `"09:11"`) is on the given date rather than today.
+///
+/// Keywords such as `"now"`, `"today"` and `"epoch"`, and offsets relative to now such as `"+1h"`,
+/// are still resolved against the system clock, ignoring the date.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_time_on_date_tz;
+/// use chrono::{NaiveDate, TimeZone, Utc};
+///
+/// let date = NaiveDate::from_ymd_opt(2018, 8, 20).unwrap();
+/// assert_eq!(parse_time_on_date_tz("09:11 + 1h", date, Utc).unwrap().single(),
+///            Some(Utc.with_ymd_and_hms(2018, 8, 20, 10, 11, 0).unwrap()));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_time_on_date_tz<S, T, Tz>(
+    timestamp: S,
+    date: NaiveDate,
+    timezone: T,
+) -> Result<LocalDateTime<Tz>, Error>
+where
+    S: AsRef<str>,
+    T: Borrow<Tz>,
+    Tz: TimeZone,
+{
+    Parser::new()
+        .anchor_date(date)
+        .parse_tz(timestamp, timezone)
+}
+
+/// Check whether a timestamp is well formed, without reading the system clock.
+///
+/// Returns `false` exactly when [`parse_timestamp_tz`] would reject the timestamp's format, numbers or time units,
+/// with [`Error::Format`], [`Error::Number`] or [`Error::TimeUnit`]. Whether the time exists in, or is ambiguous in,
+/// a particular timezone is not checked, and nor is overflow.
+///
+/// Relative times are resolved against the epoch in UTC, so a weekday prefix of a relative time (e.g. `"Mon today"`)
+/// is checked against the epoch rather than against now.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::is_valid_timestamp;
+/// assert!(is_valid_timestamp("2018-08-20 09:11:12 + 1h"));
+/// assert!(is_valid_timestamp("3s ago"));
+/// assert!(!is_valid_timestamp("2018-08-20 09:11:12 + 1x"));
+/// assert!(!is_valid_timestamp("tomorrow-ish"));
+/// ```
+pub fn is_valid_timestamp<S: AsRef<str>>(timestamp: S) -> bool {
+    let res = parse_timestamp_tz_at(timestamp, Utc, DateTime::UNIX_EPOCH);
+    !matches!(
+        res,
+        Err(Error::Format(_) | Error::Number { .. } | Error::TimeUnit { .. })
+    )
+}
+
+/// Split a timestamp into its tokens, each with the byte range it spans in the timestamp,
+/// such as to highlight its syntax.
+///
+/// The timestamp is tokenized as [`parse_timestamp_tz`] parses it, so a timestamp which fails to parse in UTC,
+/// with relative times resolved against the epoch, is rejected with the error parsing it.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{tokenize, Token};
+/// assert_eq!(
+///     tokenize("2018-08-20 + 1h").unwrap(),
+///     vec![
+///         Token::DatePart(0..10),
+///         Token::Whitespace(10..11),
+///         Token::Sign(11..12),
+///         Token::Whitespace(12..13),
+///         Token::Number(13..14),
+///         Token::Unit(14..15),
+///     ]
+/// );
+/// ```
+pub fn tokenize(ts: &str) -> Result<Vec<Token>, Error> {
+    token::tokenize(ts)
+}
+
+/// Parse a time span into a `Duration`.
+///
+/// The time span follows the same grammar as the offset of a timestamp,
+/// but may not be prefixed with a `+` or `-`.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_duration;
+/// use chrono::Duration;
+///
+/// assert_eq!(parse_duration("3hours").unwrap(), Duration::hours(3));
+/// assert_eq!(parse_duration("2d 5h").unwrap(), Duration::days(2) + Duration::hours(5));
+/// assert_eq!(parse_duration("30m22s").unwrap(), Duration::minutes(30) + Duration::seconds(22));
+/// assert!(parse_duration("+30m").is_err());
+/// ```
+pub fn parse_duration<S: AsRef<str>>(span: S) -> Result<Duration, Error> {
+    let span = span.as_ref();
+    let span_nw = strip_whitespace(span);
+
+    if span_nw.is_empty() {
+        return Err(Error::Format("Time span cannot be empty".to_owned()));
+    }
+    if span_nw.starts_with(['+', '-']) {
+        return Err(
+            Error::Format("Time span cannot be prefixed with a `+` or `-`".to_owned())
+                .with_input(span),
+        );
     }
 
-    /*
-     * A timestamp is composed of two parts: a time and an offset relative to that time.
-     *
-     * In the general case, the time is separated from the offset by either a '+' or '-'
-     * character which denotes how the offset is relative to that time.
-     *
-     * There are a few special cases which are not handled by the general case.
-     * These are detected, and handled, before applying the general case algorithm.
-     */
-
-    // Special Case 1 - a suffix of " left" or " ago", or a prefix of '+' or '-':
-    //  - the time is now.
-    //  - the offset consists of the remaining characters added to or subtracted from the current time, respectively.
-    if ts.starts_with('+') {
-        let now = Utc::now().with_timezone(tz);
-        let offset = parse_offset(&ts_nw[1..])?;
-        return Ok(LocalDateTime::Single(now + offset));
+    Parser::new()
+        .parse_offset(&span_nw)
+        .map_err(|e| e.map_position(|at| unstripped_position(span, at)))
+        .and_then(|span| span.duration())
+        .map_err(|e| e.with_input(span))
+}
+
+/// Parse a time span into a `Duration` without allocating, scanning the time span in place.
+///
+/// The time span follows the grammar of [`parse_duration`], and parses to the same result,
+/// except that an ISO 8601 duration is not accepted.
+/// Whitespace separating a number from its time unit, or one time span from the next, is skipped in place.
+/// Only whitespace splitting a number or time unit (e.g. `"1 1s"`, which is eleven seconds) allocates,
+/// as the time span is then parsed as by [`parse_duration`], as does an error, to describe itself.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_duration_in_place;
+/// use chrono::Duration;
+///
+/// assert_eq!(parse_duration_in_place("1h 2m 3s").unwrap(), Duration::seconds(3723));
+/// assert_eq!(parse_duration_in_place(" 2 days ").unwrap(), Duration::days(2));
+/// assert_eq!(parse_duration_in_place("1 1s").unwrap(), Duration::seconds(11));
+/// ```
+pub fn parse_duration_in_place(span: &str) -> Result<Duration, Error> {
+    let span_trimmed = span.trim_start();
+    if span_trimmed.is_empty() {
+        return Err(Error::Format("Time span cannot be empty".to_owned()));
     }
-    if ts.ends_with(" left") {
-        let now = Utc::now().with_timezone(tz);
-        let offset = parse_offset(&ts_nw[..(ts_nw.len() - 4)])?;
-        return Ok(LocalDateTime::Single(now + offset));
+    if span_trimmed.starts_with(['+', '-']) {
+        return Err(
+            Error::Format("Time span cannot be prefixed with a `+` or `-`".to_owned())
+                .with_input(span),
+        );
+    }
+    if span_trimmed.starts_with('P') {
+        return Err(
+            Error::Format("An ISO 8601 duration is not parsed in place".to_owned())
+                .with_input(span),
+        );
     }
 
-    if ts.starts_with('-') {
-        let now = Utc::now().with_timezone(tz);
-        let offset = parse_offset(&ts_nw[1..])?;
-        return Ok(LocalDateTime::Single(now - offset));
+    // whitespace within a number or time unit is not skipped in place, so it is removed first
+    if splits_component(span_trimmed) {
+        return parse_duration(span);
     }
-    if ts.ends_with(" ago") {
-        let now = Utc::now().with_timezone(tz);
-        let offset = parse_offset(&ts_nw[..(ts_nw.len() - 3)])?;
-        return Ok(LocalDateTime::Single(now - offset));
+
+    let leading = span.len() - span_trimmed.len();
+    Parser::new()
+        .parse_offset(span_trimmed)
+        .map_err(|e| e.map_position(|at| leading + at))
+        .and_then(|span| span.duration())
+        .map_err(|e| e.with_input(span))
+}
+
+/// Parse an offset relative to now into a signed `Duration`, without resolving the time it is relative to.
+///
+/// The offset is either prefixed with a `+` or `-`, possibly chaining several offsets (e.g. `"+1h - 30m"`),
+/// prefixed with `"in "`, or suffixed with `" left"`, `" ago"` or `" from now"`.
+/// A time such as `"now"` or `"2018-08-20"` is rejected.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_offset_signed;
+/// use chrono::Duration;
+///
+/// assert_eq!(parse_offset_signed("+2h").unwrap(), Duration::hours(2));
+/// assert_eq!(parse_offset_signed("-3s").unwrap(), Duration::seconds(-3));
+/// assert_eq!(parse_offset_signed("3s ago").unwrap(), Duration::seconds(-3));
+/// assert_eq!(parse_offset_signed("2h left").unwrap(), Duration::hours(2));
+/// assert_eq!(parse_offset_signed("in 2h").unwrap(), Duration::hours(2));
+/// assert!(parse_offset_signed("now + 2h").is_err());
+/// ```
+pub fn parse_offset_signed<S: AsRef<str>>(ts: S) -> Result<Duration, Error> {
+    let ts = ts.as_ref();
+    Parser::new()
+        .parse_signed_offset(ts)
+        .and_then(|span| span.duration())
+        .map_err(|e| e.with_input(ts))
+}
+
+/// Add a time span to a time, applying months and years as calendar months and every other time unit as a
+/// fixed duration.
+///
+/// A calendar month moves the date to the same day of the following month, clamping to the end of that month,
+/// and keeps the time of day, as with [`Parser::months_as_calendar`]. Days and weeks, however, remain
+/// fixed durations. The time span follows the grammar of [`parse_duration`], but may be prefixed with a `-`
+/// to subtract it. A time made ambiguous in its timezone is resolved to its earliest.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::add_calendar_span;
+/// use chrono::{TimeZone, Utc};
+///
+/// let dt = Utc.with_ymd_and_hms(2018, 1, 31, 9, 0, 0).unwrap();
+/// assert_eq!(add_calendar_span(dt, "1M 2h").unwrap(), Utc.with_ymd_and_hms(2018, 2, 28, 11, 0, 0).unwrap());
+/// assert_eq!(add_calendar_span(dt, "-1y").unwrap(), Utc.with_ymd_and_hms(2017, 1, 31, 9, 0, 0).unwrap());
+/// ```
+pub fn add_calendar_span<Tz: TimeZone>(
+    dt: DateTime<Tz>,
+    span: &str,
+) -> Result<DateTime<Tz>, Error> {
+    let span_nw = strip_whitespace(span);
+    let (negative, span_nw) = match span_nw.strip_prefix('-') {
+        Some(span_nw) => (true, span_nw),
+        None => (false, span_nw.as_ref()),
+    };
+    if span_nw.is_empty() {
+        return Err(Error::Format("Time span cannot be empty".to_owned()).with_input(span));
     }
 
-    // Special Case 2 - a prefix of '@':
-    //  - the time is the unix epoch.
-    //  - the offset consists of the remaining characters added to the epoch time.
-    if ts.starts_with('@') {
-        let epoch = tz.timestamp_opt(0, 0).unwrap();
-        let offset = parse_offset(&ts_nw[1..])?;
-        return Ok(LocalDateTime::Single(epoch + offset));
+    let parser = Parser::new().months_as_calendar(true);
+    let offset = parser
+        .parse_offset(span_nw)
+        .map_err(|e| e.map_position(|at| unstripped_position(span, at + usize::from(negative))))
+        .map_err(|e| e.with_input(span))?;
+    // only months and years are calendar units here, so days and weeks are folded back into a duration
+    let sign = if negative { -1 } else { 1 };
+    let offset = parser::Span {
+        months: sign * offset.months,
+        days: 0,
+        nsecs: i128::from(sign)
+            * (offset.nsecs + i128::from(offset.days) * i128::from(NSEC_PER_DAY)),
+    };
+    offset
+        .apply(LocalDateTime::Single(dt), &parser)
+        .map(LocalDateTime::earliest)
+        .map_err(|e| e.with_input(span))
+}
+
+/// Parse a time span into a [`std::time::Duration`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::parse_std_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_std_duration("1h30m").unwrap(), Duration::from_secs(5400));
+/// assert_eq!(parse_std_duration("2s 5ms").unwrap(), Duration::from_millis(2005));
+/// ```
+pub fn parse_std_duration<S: AsRef<str>>(span: S) -> Result<core::time::Duration, Error> {
+    let duration = parse_duration(span)?;
+    duration.to_std().map_err(|e| Error::Number {
+        msg: format!("Cannot convert time span `{duration}` into a std::time::Duration: {e}"),
+        source: None,
+    })
+}
+
+/// The time units understood in a time span, such as `"h"` and `"hours"`, for autocompletion or validation.
+///
+/// Custom time units defined with [`Parser::add_unit`] are not included.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::time_units;
+/// assert!(time_units().any(|unit| unit == "weeks"));
+/// ```
+pub fn time_units() -> impl Iterator<Item = &'static str> {
+    TIME_UNITS.iter().map(|&(unit, _)| unit)
+}
+
+/// The keywords which make up a time on their own, such as `"now"` and `"today"`.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::time_keywords;
+/// assert!(time_keywords().contains(&"tomorrow"));
+/// ```
+pub fn time_keywords() -> &'static [&'static str] {
+    &parser::KEYWORDS
+}
+
+/// Format a `Duration` into a time span, the inverse of [`parse_duration`] for a duration within [`MAX_SPAN`].
+///
+/// The time span is made up of the largest to smallest time units, omitting zero components:
+/// `y`, `M`, `w`, `d`, `h`, `m`, `s`, `ms`, `us` and `ns`.
+/// A zero duration is formatted as `"0s"`.
+///
+/// A negative duration is prefixed with a `-`. Such a time span is rejected by [`parse_duration`],
+/// but may be used as the offset of a timestamp, e.g. `"now -1h 30m"`.
+///
+/// A duration of a greater magnitude than [`MAX_SPAN`], such as `Duration::MAX`, is still formatted,
+/// but its time span is rejected by [`parse_duration`] with [`Error::Overflow`].
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{format_duration, parse_duration};
+/// use chrono::Duration;
+///
+/// assert_eq!(format_duration(Duration::seconds(3723)), "1h 2m 3s");
+/// assert_eq!(format_duration(Duration::milliseconds(1500)), "1s 500ms");
+/// assert_eq!(format_duration(-Duration::days(8)), "-1w 1d");
+///
+/// let duration = Duration::days(400) + Duration::microseconds(5);
+/// assert_eq!(parse_duration(format_duration(duration)).unwrap(), duration);
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    let nsecs = duration_nsecs(duration);
+    if nsecs == 0 {
+        return "0s".to_owned();
     }
 
-    // General Case - the time is separated from the offset by either a '+' or '-'.
-    // Note: need to find " +" and " -" here because strftime date formats may contain the '-' character,
-    //       but with no leading whitespaces.
-    match (ts.find(" +"), ts.find(" -")) {
-        (Some(_), Some(_)) => Err(Error::Format(
-            "Timestamp cannot contain both a `+` and `-`".to_owned(),
-        )),
-        (Some(p), None) => {
-            let p_nw = ts_nw.find('+').unwrap();
-            let time = parse_time(&ts[..p], tz)?;
-            let offset = parse_offset(&ts_nw[(p_nw + 1)..])?;
-            Ok(time + offset)
-        }
-        (None, Some(m)) => {
-            let m_nw = ts_nw.rfind('-').unwrap();
-            let time = parse_time(&ts[..m], tz)?;
-            let offset = parse_offset(&ts_nw[(m_nw + 1)..])?;
-            Ok(time - offset)
-        }
-        (None, None) => {
-            let time = parse_time(ts, tz)?;
-            Ok(time)
+    let mut remaining = nsecs.unsigned_abs();
+    let mut components = Vec::new();
+    for (unit, multiplier) in DURATION_UNITS {
+        let multiplier = multiplier as u128;
+        let number = remaining / multiplier;
+        remaining %= multiplier;
+        if number > 0 {
+            components.push(format!("{number}{unit}"));
         }
     }
+
+    let span = components.join(" ");
+    if nsecs < 0 {
+        format!("-{span}")
+    } else {
+        span
+    }
 }
 
-/// Parse a point-in-time into a `DateTime` with the given timezone.
-///
-/// * `ts` - a str of a time with whitespace intact.
-/// * `tz` - the time zone to use.
-fn parse_time<Tz: TimeZone>(ts: &str, tz: &Tz) -> Result<LocalDateTime<Tz>, Error> {
-    let dt = match ts {
-        "now" => LocalDateTime::Single(Utc::now().with_timezone(tz)),
-        "epoch" => LocalDateTime::Single(tz.timestamp_opt(0, 0).unwrap()),
-        "today" => LocalDateTime::from_date(naive_today(tz), tz)?,
-        "yesterday" => LocalDateTime::from_date(naive_today(tz) - Days::new(1), tz)?,
-        "tomorrow" => LocalDateTime::from_date(naive_today(tz) + Days::new(1), tz)?,
-        ts => match ts.find('.') {
-            // an optional '.' separates the seconds and microseconds components
-            Some(p) => {
-                let ts_t = &ts[..p];
-                let ndt = NaiveDateTime::parse_from_str(ts_t, "%y-%m-%d %H:%M:%S")
-                    .or_else(|_| NaiveDateTime::parse_from_str(ts_t, "%Y-%m-%d %H:%M:%S"))
-                    .or_else(|_| {
-                        NaiveTime::parse_from_str(ts_t, "%H:%M:%S")
-                            .map(|nt| naive_today(tz).and_time(nt))
-                    })
-                    .map_err(|_| {
-                        Error::Format(format!("Cannot parse `{ts_t}` before '.' into a time"))
-                    })?;
-
-                let ts_u = &ts[(p + 1)..];
-                let usecs: i64 = ts_u.parse().map_err(|e| {
-                    Error::Number(format!(
-                        "Cannot parse `{ts_u}` after '.' into a number: {e}"
-                    ))
-                })?;
-
-                let ndt = ndt + Duration::microseconds(usecs);
-                LocalDateTime::from_datetime(ndt, tz)?
-            }
-            None => NaiveDateTime::parse_from_str(ts, "%y-%m-%d %H:%M:%S")
-                .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S"))
-                .or_else(|_| NaiveDateTime::parse_from_str(ts, "%y-%m-%d %H:%M"))
-                .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M"))
-                .or_else(|_| {
-                    NaiveDate::parse_from_str(ts, "%y-%m-%d")
-                        .map(|nd| nd.and_hms_opt(0, 0, 0).unwrap())
-                })
-                .or_else(|_| {
-                    NaiveDate::parse_from_str(ts, "%Y-%m-%d")
-                        .map(|nd| nd.and_hms_opt(0, 0, 0).unwrap())
-                })
-                .or_else(|_| {
-                    NaiveTime::parse_from_str(ts, "%H:%M:%S").map(|nt| naive_today(tz).and_time(nt))
-                })
-                .or_else(|_| {
-                    NaiveTime::parse_from_str(ts, "%H:%M").map(|nt| naive_today(tz).and_time(nt))
-                })
-                .map_err(|_| Error::Format(format!("Cannot parse `{ts}` into a time")))
-                .and_then(|ndt| LocalDateTime::from_datetime(ndt, tz))?,
-        },
+/// Break a `Duration` into the number of each time unit it is made up of, the structured counterpart
+/// of [`format_duration`].
+///
+/// Each component is what remains once the larger time units are taken out, using the same weights as parsing,
+/// so months and years are the approximations of 30.44 and 365.25 days respectively.
+/// Every component of a negative duration is negative or zero.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{duration_components, DurationComponents};
+/// use chrono::Duration;
+///
+/// let components = duration_components(Duration::seconds(90061));
+/// assert_eq!((components.days, components.hours, components.minutes, components.seconds), (1, 1, 1, 1));
+/// assert_eq!(duration_components(-Duration::milliseconds(1500)),
+///            DurationComponents { seconds: -1, micros: -500_000, ..Default::default() });
+/// ```
+pub fn duration_components(duration: Duration) -> DurationComponents {
+    let nsecs = duration_nsecs(duration);
+    let mut remaining = nsecs.abs();
+    let mut take = |multiplier: i64| {
+        let number = remaining / i128::from(multiplier);
+        remaining %= i128::from(multiplier);
+        // a duration in nanoseconds is at most an i64 of seconds, so each number of its time units fits in an i64
+        let number = number as i64;
+        if nsecs < 0 {
+            -number
+        } else {
+            number
+        }
     };
-    Ok(dt)
+    DurationComponents {
+        years: take(NSEC_PER_YEAR),
+        months: take(NSEC_PER_MONTH),
+        weeks: take(NSEC_PER_WEEK),
+        days: take(NSEC_PER_DAY),
+        hours: take(NSEC_PER_HOUR),
+        minutes: take(NSEC_PER_MINUTE),
+        seconds: take(NSEC_PER_SEC),
+        micros: take(NSEC_PER_USEC),
+    }
+}
+
+/// The time units a `Duration` is broken into, from the largest to the smallest.
+#[rustfmt::skip]
+const DURATION_UNITS: [(&str, i64); 10] = [
+    ("y", NSEC_PER_YEAR),
+    ("M", NSEC_PER_MONTH),
+    ("w", NSEC_PER_WEEK),
+    ("d", NSEC_PER_DAY),
+    ("h", NSEC_PER_HOUR),
+    ("m", NSEC_PER_MINUTE),
+    ("s", NSEC_PER_SEC),
+    ("ms", NSEC_PER_MSEC),
+    ("us", NSEC_PER_USEC),
+    ("ns", NSEC_PER_NSEC),
+];
+
+/// The exact number of nanoseconds in a `Duration`.
+fn duration_nsecs(duration: Duration) -> i128 {
+    // a duration's seconds and nanoseconds share the same sign
+    i128::from(duration.num_seconds()) * i128::from(NSEC_PER_SEC)
+        + i128::from(duration.subsec_nanos())
 }
 
-/// Parse and combine all time spans into a single duration.
+/// Find the position of the whitespace preceding the first '+' or '-' sign.
 ///
-/// * `ts_nw` - a str of time spans with whitespace removed.
-fn parse_offset(mut ts_nw: &str) -> Result<Duration, Error> {
-    let mut total_usecs: i64 = 0;
-    loop {
-        if ts_nw.is_empty() {
-            return Ok(Duration::microseconds(total_usecs));
+/// Note: need to find " +" and " -" here because strftime date formats may contain the '-' character,
+///       but with no leading whitespaces.
+fn find_sign(ts: &str) -> Option<usize> {
+    match (ts.find(" +"), ts.find(" -")) {
+        (Some(p), Some(m)) => Some(p.min(m)),
+        (p, m) => p.or(m),
+    }
+}
+
+/// Weigh the fractional part of a time span's number by its multiplier,
+/// rounding to the nearest nanosecond.
+///
+/// * `fraction` - a str of the ascii digits following the '.' separator.
+/// * `multiplier` - the nanosecond weight of the time span's unit.
+fn fraction_nsecs(fraction: &str, multiplier: i64) -> i128 {
+    // more digits than this cannot affect the result, and would overflow the multiplication
+    let fraction = &fraction[..fraction.len().min(18)];
+    if fraction.is_empty() {
+        return 0;
+    }
+
+    let scale = 10_i128.pow(fraction.len() as u32);
+    let value: i128 = fraction.parse().unwrap();
+    (value * i128::from(multiplier) + scale / 2) / scale
+}
+
+/// Map a byte position in a str with all whitespace removed back to the byte position in the original str.
+///
+/// A position at the end of the stripped str maps to the end of the original str.
+fn unstripped_position(ts: &str, position: usize) -> usize {
+    let mut stripped = 0;
+    for (i, c) in ts.char_indices().filter(|(_, c)| !c.is_whitespace()) {
+        if stripped == position {
+            return i;
         }
+        stripped += c.len_utf8();
+    }
+    ts.len()
+}
+
+/// Remove all whitespace from a str, only allocating when there is whitespace to remove.
+fn strip_whitespace(ts: &str) -> Cow<'_, str> {
+    if ts.contains(char::is_whitespace) {
+        Cow::Owned(ts.chars().filter(|&c| !c.is_whitespace()).collect())
+    } else {
+        Cow::Borrowed(ts)
+    }
+}
 
-        /*
-         * Time spans have the format: "<number><multipler>"
-         */
-
-        // look for digit characters to make up the `number`
-        // followed by alphabetic characters to make up the `multiplier`
-        let (digits, ts_tail) = partition_predicate(ts_nw, |c| c.is_ascii_digit());
-        let (letters, ts_tail) = partition_predicate(ts_tail, char::is_alphabetic);
-        ts_nw = ts_tail;
-
-        // parse the `number` and `multipler` strings into i64
-        let number: i64 = digits
-            .parse()
-            .map_err(|e| Error::Number(format!("Cannot parse `{digits}` into a number: {e}")))?;
-        let Some(&multiplier) = USEC_MULTIPLIER.get(letters) else {
-            return Err(Error::TimeUnit(letters.to_owned()));
-        };
-
-        let Some(usecs) = number
-            .checked_mul(multiplier)
-            .and_then(|usec| usec.checked_add(total_usecs))
-        else {
-            return Err(Error::Number(format!(
-                "Offset microseconds overflowed: total_usecs `{total_usecs}` number `{number}` multiplier `{multiplier}`"
-            )));
-        };
-        // increment the total microsecond offset returning a failure on an overflow
-        total_usecs = usecs;
+/// Whether whitespace splits a number or time unit of time spans, e.g. `"1 1s"` or `"1 h ours"`,
+/// rather than only separating one component from the next.
+fn splits_component(ts: &str) -> bool {
+    let kind = |c: char| match c {
+        '0'..='9' | '.' => Some(true),
+        c if c.is_alphabetic() => Some(false),
+        _ => None,
+    };
+    let mut prev = None;
+    let mut spaced = false;
+    for c in ts.chars() {
+        if c.is_whitespace() {
+            spaced = true;
+            continue;
+        }
+        if spaced && prev.is_some() && kind(c).is_some() && prev.and_then(kind) == kind(c) {
+            return true;
+        }
+        prev = Some(c);
+        spaced = false;
     }
+    false
 }
 
-fn naive_today<Tz: TimeZone>(tz: &Tz) -> NaiveDate {
-    Utc::now().with_timezone(tz).date_naive()
+/// The date of `now` in the given timezone.
+fn naive_today<Tz: TimeZone>(tz: &Tz, now: &DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(tz).date_naive()
 }
 
 /// Partition a str by a given predicate.
@@ -1,31 +1,122 @@
-use std::ops::{Add, Sub};
+use alloc::string::ToString;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{self, Chain, Once};
+use core::mem;
+use core::ops::{Add, Sub};
+use core::option;
 
 use chrono::LocalResult;
-use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone};
+use chrono::{DateTime, Duration, NaiveDateTime, SecondsFormat, TimeZone};
 
 use crate::Error;
 
+const SECS_PER_DAY: i64 = 86_400;
+
 /// The conversion time returned by [`NaiveDateTime::and_local_timezone`]
 ///
 /// [`NaiveDateTime::and_local_timezone`]: chrono::NaiveDateTime::and_local_timezone
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub enum LocalDateTime<Tz: TimeZone> {
     Single(DateTime<Tz>),
     Ambiguous(DateTime<Tz>, DateTime<Tz>),
 }
 
+impl<Tz: TimeZone, Tz2: TimeZone> PartialEq<LocalDateTime<Tz2>> for LocalDateTime<Tz> {
+    fn eq(&self, other: &LocalDateTime<Tz2>) -> bool {
+        match (self, other) {
+            (Self::Single(dt), LocalDateTime::Single(other)) => dt == other,
+            (Self::Ambiguous(dt1, dt2), LocalDateTime::Ambiguous(other1, other2)) => {
+                dt1 == other1 && dt2 == other2
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<Tz: TimeZone> Eq for LocalDateTime<Tz> {}
+
+/// Orders by the earliest possible conversion time, so an ambiguous time sorts where its first occurrence does.
+///
+/// Ties are broken by the latest possible conversion time, then by `Single` preceding `Ambiguous`,
+/// which keeps the order total and consistent with equality.
+impl<Tz: TimeZone, Tz2: TimeZone> PartialOrd<LocalDateTime<Tz2>> for LocalDateTime<Tz> {
+    fn partial_cmp(&self, other: &LocalDateTime<Tz2>) -> Option<Ordering> {
+        Some(self.order_key().cmp(&other.order_key()))
+    }
+}
+
+impl<Tz: TimeZone> Ord for LocalDateTime<Tz> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order_key().cmp(&other.order_key())
+    }
+}
+
+impl<Tz: TimeZone> Copy for LocalDateTime<Tz> where Tz::Offset: Copy {}
+
+impl<Tz: TimeZone> Hash for LocalDateTime<Tz> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        for dt in self {
+            dt.hash(state);
+        }
+    }
+}
+
 impl<Tz: TimeZone> TryFrom<LocalResult<DateTime<Tz>>> for LocalDateTime<Tz> {
     type Error = Error;
 
     fn try_from(res: LocalResult<DateTime<Tz>>) -> Result<Self, Self::Error> {
         match res {
-            LocalResult::None => Err(Error::Never),
+            LocalResult::None => Err(Error::Never(None)),
             LocalResult::Single(dt) => Ok(LocalDateTime::Single(dt)),
             LocalResult::Ambiguous(dt1, dt2) => Ok(LocalDateTime::Ambiguous(dt1, dt2)),
         }
     }
 }
 
+impl<Tz: TimeZone> From<DateTime<Tz>> for LocalDateTime<Tz> {
+    fn from(dt: DateTime<Tz>) -> Self {
+        LocalDateTime::Single(dt)
+    }
+}
+
+/// Converts into the conversion time when it is unique, as with [`LocalDateTime::into_single`].
+impl<Tz: TimeZone> TryFrom<LocalDateTime<Tz>> for DateTime<Tz> {
+    type Error = Error;
+
+    fn try_from(time: LocalDateTime<Tz>) -> Result<Self, Self::Error> {
+        time.into_single()
+    }
+}
+
+/// Converts into chrono's own `LocalResult`, as either `LocalResult::Single` or `LocalResult::Ambiguous`.
+///
+/// A time which does not exist in the timezone, `LocalResult::None`, is instead reported when parsing
+/// as [`Error::Never`], which callers may map back into `LocalResult::None`.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_timestamp_tz_at, Error};
+/// use chrono::{DateTime, LocalResult, TimeZone, Utc};
+///
+/// let result = match parse_timestamp_tz_at("2018-08-20 09:11:12", Utc, DateTime::UNIX_EPOCH) {
+///     Ok(time) => time.into(),
+///     Err(Error::Never(_)) => LocalResult::None,
+///     Err(e) => panic!("{e}"),
+/// };
+/// assert_eq!(result, Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12));
+/// ```
+impl<Tz: TimeZone> From<LocalDateTime<Tz>> for LocalResult<DateTime<Tz>> {
+    fn from(time: LocalDateTime<Tz>) -> Self {
+        match time {
+            LocalDateTime::Single(dt) => LocalResult::Single(dt),
+            LocalDateTime::Ambiguous(dt1, dt2) => LocalResult::Ambiguous(dt1, dt2),
+        }
+    }
+}
+
 impl<Tz: TimeZone> LocalDateTime<Tz> {
     /// Returns `Some` when the conversion time is unique, or `None` otherwise.
     pub fn single(self) -> Option<DateTime<Tz>> {
@@ -35,6 +126,36 @@ impl<Tz: TimeZone> LocalDateTime<Tz> {
         }
     }
 
+    /// Returns the conversion time when it is unique, or [`Error::Ambiguous`] with both candidates otherwise.
+    pub fn into_single(self) -> Result<DateTime<Tz>, Error> {
+        match self {
+            Self::Single(dt) => Ok(dt),
+            ambiguous => Err(Error::Ambiguous(
+                ambiguous.map(|dt| dt.fixed_offset()).to_string(),
+            )),
+        }
+    }
+
+    /// Returns the conversion time when it is unique, panicking otherwise.
+    ///
+    /// This is a convenience for tests, where its panic names both candidates of an ambiguous time
+    /// rather than the bare `None` of `single().unwrap()`.
+    /// Outside of tests, an ambiguous time is better handled with [`single`](Self::single)
+    /// or [`into_single`](Self::into_single).
+    ///
+    /// # Panics
+    /// Panics if the conversion time is ambiguous.
+    #[track_caller]
+    pub fn unwrap_single(self) -> DateTime<Tz> {
+        match self {
+            Self::Single(dt) => dt,
+            ambiguous => panic!(
+                "called `LocalDateTime::unwrap_single()` on an ambiguous time: {}",
+                ambiguous.map(|dt| dt.fixed_offset())
+            ),
+        }
+    }
+
     /// Returns the earliest possible conversion time.
     pub fn earliest(self) -> DateTime<Tz> {
         match self {
@@ -48,40 +169,229 @@ impl<Tz: TimeZone> LocalDateTime<Tz> {
             Self::Single(dt) | Self::Ambiguous(_, dt) => dt,
         }
     }
-}
 
-impl<Tz: TimeZone> LocalDateTime<Tz> {
-    pub(super) fn from_date(date: NaiveDate, tz: &Tz) -> Result<LocalDateTime<Tz>, Error> {
-        tz.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
-            .try_into()
+    /// Returns an iterator over the possible conversion times, from earliest to latest.
+    pub fn iter(&self) -> Chain<Once<&DateTime<Tz>>, option::IntoIter<&DateTime<Tz>>> {
+        self.into_iter()
+    }
+
+    /// Adds a duration to each possible conversion time, returning `None` when any would overflow.
+    pub fn checked_add(self, rhs: Duration) -> Option<Self> {
+        self.try_map(|dt| dt.checked_add_signed(rhs).ok_or(())).ok()
+    }
+
+    /// Subtracts a duration from each possible conversion time, returning `None` when any would overflow.
+    pub fn checked_sub(self, rhs: Duration) -> Option<Self> {
+        self.try_map(|dt| dt.checked_sub_signed(rhs).ok_or(())).ok()
+    }
+
+    /// Maps each possible conversion time with a function, which may convert it into another timezone.
+    pub fn map<U, F>(self, mut f: F) -> LocalDateTime<U>
+    where
+        U: TimeZone,
+        F: FnMut(DateTime<Tz>) -> DateTime<U>,
+    {
+        match self {
+            Self::Single(dt) => LocalDateTime::Single(f(dt)),
+            Self::Ambiguous(dt1, dt2) => LocalDateTime::Ambiguous(f(dt1), f(dt2)),
+        }
     }
 
+    /// Maps each possible conversion time with a fallible function, returning the first error.
+    pub fn try_map<U, E, F>(self, mut f: F) -> Result<LocalDateTime<U>, E>
+    where
+        U: TimeZone,
+        F: FnMut(DateTime<Tz>) -> Result<DateTime<U>, E>,
+    {
+        match self {
+            Self::Single(dt) => Ok(LocalDateTime::Single(f(dt)?)),
+            Self::Ambiguous(dt1, dt2) => Ok(LocalDateTime::Ambiguous(f(dt1)?, f(dt2)?)),
+        }
+    }
+}
+
+impl<Tz: TimeZone> LocalDateTime<Tz> {
     pub(super) fn from_datetime(
         datetime: NaiveDateTime,
         tz: &Tz,
     ) -> Result<LocalDateTime<Tz>, Error> {
-        tz.from_local_datetime(&datetime).try_into()
+        LocalDateTime::try_from(tz.from_local_datetime(&datetime))
+            .map_err(|_| Error::Never(Some(datetime)))
+    }
+
+    fn order_key(&self) -> (NaiveDateTime, NaiveDateTime, bool) {
+        match self {
+            Self::Single(dt) => (dt.naive_utc(), dt.naive_utc(), false),
+            Self::Ambiguous(dt1, dt2) => (dt1.naive_utc(), dt2.naive_utc(), true),
+        }
+    }
+
+    /// The first time following a local time which is skipped by the timezone,
+    /// such as during a daylight saving time spring-forward.
+    pub(super) fn after_gap(datetime: NaiveDateTime, tz: &Tz) -> Option<DateTime<Tz>> {
+        // offsets are less than a day, so the local time is reached within a day either side of it as UTC
+        let secs = datetime.and_utc().timestamp();
+        let (mut lo, mut hi) = (secs - SECS_PER_DAY, secs + SECS_PER_DAY);
+
+        // search for the earliest instant whose local time follows the skipped local time
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let dt = DateTime::from_timestamp(mid, 0)?.with_timezone(tz);
+            if dt.naive_local() > datetime {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Some(DateTime::from_timestamp(lo, 0)?.with_timezone(tz))
+    }
+}
+
+impl<Tz: TimeZone> IntoIterator for LocalDateTime<Tz> {
+    type Item = DateTime<Tz>;
+    type IntoIter = Chain<Once<DateTime<Tz>>, option::IntoIter<DateTime<Tz>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let (dt1, dt2) = match self {
+            Self::Single(dt) => (dt, None),
+            Self::Ambiguous(dt1, dt2) => (dt1, Some(dt2)),
+        };
+        iter::once(dt1).chain(dt2)
+    }
+}
+
+impl<'a, Tz: TimeZone> IntoIterator for &'a LocalDateTime<Tz> {
+    type Item = &'a DateTime<Tz>;
+    type IntoIter = Chain<Once<&'a DateTime<Tz>>, option::IntoIter<&'a DateTime<Tz>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let (dt1, dt2) = match self {
+            LocalDateTime::Single(dt) => (dt, None),
+            LocalDateTime::Ambiguous(dt1, dt2) => (dt1, Some(dt2)),
+        };
+        iter::once(dt1).chain(dt2)
     }
 }
 
+/// Formats each possible conversion time in RFC 3339, separated by `" or "` when ambiguous.
+///
+/// A precision selects the fewest fractional second digits, of 0, 3, 6 or 9, covering it.
+impl<Tz: TimeZone> fmt::Display for LocalDateTime<Tz>
+where
+    Tz::Offset: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secform = match f.precision() {
+            None => SecondsFormat::AutoSi,
+            Some(0) => SecondsFormat::Secs,
+            Some(1..=3) => SecondsFormat::Millis,
+            Some(4..=6) => SecondsFormat::Micros,
+            Some(_) => SecondsFormat::Nanos,
+        };
+        for (i, dt) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" or ")?;
+            }
+            f.write_str(&dt.to_rfc3339_opts(secform, false))?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds a duration to each possible conversion time.
+///
+/// # Panics
+/// Panics when the resulting time would overflow, which [`LocalDateTime::checked_add`] instead returns as `None`.
 impl<Tz: TimeZone> Add<Duration> for LocalDateTime<Tz> {
     type Output = Self;
 
     fn add(self, rhs: Duration) -> Self::Output {
-        match self {
-            Self::Single(dt) => Self::Single(dt + rhs),
-            Self::Ambiguous(dt1, dt2) => Self::Ambiguous(dt1 + rhs, dt2 + rhs),
-        }
+        self.map(|dt| dt + rhs)
     }
 }
 
+/// Subtracts a duration from each possible conversion time.
+///
+/// # Panics
+/// Panics when the resulting time would overflow, which [`LocalDateTime::checked_sub`] instead returns as `None`.
 impl<Tz: TimeZone> Sub<Duration> for LocalDateTime<Tz> {
     type Output = Self;
 
     fn sub(self, rhs: Duration) -> Self::Output {
+        self.map(|dt| dt - rhs)
+    }
+}
+
+/// Serializes a single time as the time itself, an RFC 3339 string such as `"2018-08-20T09:11:12Z"`,
+/// and an ambiguous time as a sequence of both times, from earliest to latest.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::LocalDateTime;
+/// use chrono::{Duration, TimeZone, Utc};
+///
+/// let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+/// assert_eq!(serde_json::to_string(&LocalDateTime::Single(dt)).unwrap(), r#""2018-11-04T05:30:00Z""#);
+/// assert_eq!(serde_json::to_string(&LocalDateTime::Ambiguous(dt, dt + Duration::hours(1))).unwrap(),
+///            r#"["2018-11-04T05:30:00Z","2018-11-04T06:30:00Z"]"#);
+/// ```
+#[cfg(feature = "serde")]
+impl<Tz: TimeZone> serde::Serialize for LocalDateTime<Tz>
+where
+    DateTime<Tz>: serde::Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         match self {
-            Self::Single(dt) => Self::Single(dt - rhs),
-            Self::Ambiguous(dt1, dt2) => Self::Ambiguous(dt1 - rhs, dt2 - rhs),
+            Self::Single(dt) => dt.serialize(serializer),
+            Self::Ambiguous(dt1, dt2) => (dt1, dt2).serialize(serializer),
+        }
+    }
+}
+
+/// Deserializes either a single time, or a sequence of the two times of an ambiguous time,
+/// as serialized by `LocalDateTime`.
+///
+/// The representation is distinguished by its shape, so requires a self-describing format such as JSON.
+#[cfg(feature = "serde")]
+impl<'de, Tz: TimeZone> serde::Deserialize<'de> for LocalDateTime<Tz>
+where
+    DateTime<Tz>: serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use core::marker::PhantomData;
+        use serde::de::{self, IntoDeserializer};
+
+        struct Visitor<Tz>(PhantomData<Tz>);
+
+        impl<'de, Tz: TimeZone> de::Visitor<'de> for Visitor<Tz>
+        where
+            DateTime<Tz>: serde::Deserialize<'de>,
+        {
+            type Value = LocalDateTime<Tz>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an RFC 3339 time, or a sequence of two RFC 3339 times")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let dt = serde::Deserialize::deserialize(v.into_deserializer())?;
+                Ok(LocalDateTime::Single(dt))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let dt1 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let dt2 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(3, &self));
+                }
+                Ok(LocalDateTime::Ambiguous(dt1, dt2))
+            }
         }
+
+        deserializer.deserialize_any(Visitor(PhantomData))
     }
 }
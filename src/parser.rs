@@ -0,0 +1,1765 @@
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::num::{IntErrorKind, ParseIntError};
+
+use chrono::offset::{FixedOffset, Offset, Utc};
+use chrono::{DateTime, Datelike, Days, Duration, DurationRound, Months, Weekday};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::{
+    find_sign, fraction_nsecs, naive_today, partition_predicate, strip_whitespace, unit_to_nsec,
+    unstripped_position, Error, LocalDateTime, MAX_SPAN, MAX_SPAN_NSECS, NSEC_PER_DAY,
+    NSEC_PER_MONTH, NSEC_PER_USEC, NSEC_PER_WEEK, NSEC_PER_YEAR,
+};
+
+/// The keywords which make up a time on their own.
+pub(crate) const KEYWORDS: [&str; 9] = [
+    "now",
+    "epoch",
+    "infinity",
+    "-infinity",
+    "today",
+    "yesterday",
+    "tomorrow",
+    "midnight",
+    "noon",
+];
+
+/// A configurable timestamp parser.
+///
+/// The free functions of this crate, such as [`parse_timestamp_tz`], parse with a default `Parser`.
+/// Each option defaults to the behavior of those functions.
+///
+/// [`parse_timestamp_tz`]: crate::parse_timestamp_tz
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::Parser;
+/// use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+///
+/// let parser = Parser::new()
+///     .months_as_calendar(true)
+///     .accept_iso_t(true)
+///     .default_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+///
+/// let now = DateTime::UNIX_EPOCH;
+/// assert_eq!(parser.parse_tz_at("2018-01-31 + 1M", Utc, now).unwrap().single(),
+///            Some(Utc.with_ymd_and_hms(2018, 2, 28, 12, 0, 0).unwrap()));
+/// assert_eq!(parser.parse_tz_at("2018-08-20T09:11:12", Utc, now).unwrap().single(),
+///            Some(Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Parser {
+    months_as_calendar: bool,
+    accept_iso_t: bool,
+    accept_rfc3339: bool,
+    default_time: NaiveTime,
+    ambiguous: Option<AmbiguousStrategy>,
+    snap_gaps: bool,
+    case_insensitive: bool,
+    truncate_now_to: Option<TimeUnit>,
+    reject_split_numbers: bool,
+    date_format: DateOrder,
+    units: BTreeMap<String, i64>,
+    accept_am_pm: bool,
+    accept_numeric_zone: bool,
+    month_len: Option<i64>,
+    year_len: Option<i64>,
+    bare_number_unit: Option<String>,
+    accept_articles: bool,
+    trim_trailing_punct: bool,
+    lenient_fields: bool,
+    allow_relative: bool,
+    require_space_before_sign: bool,
+    anchor_date: Option<NaiveDate>,
+}
+
+/// How a [`Parser`] resolves a time which is ambiguous in the given timezone,
+/// such as a local time repeated by a daylight saving time fall-back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbiguousStrategy {
+    /// Resolve to the earliest possible time.
+    Earliest,
+    /// Resolve to the latest possible time.
+    Latest,
+    /// Reject the time with [`Error::Ambiguous`].
+    Reject,
+}
+
+/// Whether a parsed time depends on the instant that relative times are resolved against.
+///
+/// A relative time, such as that of `"now + 1h"` or `"today"`, is only valid at the instant it was parsed,
+/// whereas an absolute time, such as that of `"2018-08-20"` or `"epoch + 1h"`, may be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The time is the same whenever the timestamp is parsed.
+    Absolute,
+    /// The time depends on the current time.
+    RelativeToNow,
+}
+
+/// The order of the date components which a [`Parser`] accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    /// Only dates separated by a `'-'` in year, month, day order (e.g. `"2018-08-20"`).
+    #[default]
+    YearMonthDay,
+    /// Also dates separated by a `'.'` in day, month, year order (e.g. `"20.08.2018"`), as is common in Germany.
+    DayMonthYear,
+}
+
+/// A unit of time which a [`Parser`] truncates the current time to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Microsecond,
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+}
+
+impl TimeUnit {
+    fn duration(self) -> Duration {
+        match self {
+            TimeUnit::Microsecond => Duration::microseconds(1),
+            TimeUnit::Millisecond => Duration::milliseconds(1),
+            TimeUnit::Second => Duration::seconds(1),
+            TimeUnit::Minute => Duration::minutes(1),
+            TimeUnit::Hour => Duration::hours(1),
+        }
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            months_as_calendar: false,
+            accept_iso_t: false,
+            accept_rfc3339: false,
+            default_time: NaiveTime::default(),
+            ambiguous: None,
+            snap_gaps: false,
+            case_insensitive: false,
+            truncate_now_to: None,
+            reject_split_numbers: false,
+            date_format: DateOrder::default(),
+            units: BTreeMap::new(),
+            accept_am_pm: false,
+            accept_numeric_zone: false,
+            month_len: None,
+            year_len: None,
+            bare_number_unit: None,
+            accept_articles: false,
+            trim_trailing_punct: false,
+            lenient_fields: false,
+            allow_relative: true,
+            require_space_before_sign: true,
+            anchor_date: None,
+        }
+    }
+}
+
+impl Parser {
+    /// Create a parser with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply month and year time spans as calendar months, rather than as fixed durations.
+    ///
+    /// A calendar month moves the date to the same day of the following month, clamping to the end of that month,
+    /// and keeps the time of day. A year is twelve calendar months.
+    /// Whole days and weeks are then applied as calendar days, keeping the time of day across a change of
+    /// UTC offset (e.g. `"today + 1w"` is midnight seven days later, even across a daylight saving transition).
+    /// Calendar months are applied to the time before calendar days, and calendar days before any other time span.
+    ///
+    /// As the time of day is kept, a calendar month or day may land in a gap skipped by a daylight saving transition
+    /// (e.g. `"2018-03-10 02:30 + 1d"` in America/New_York), which is rejected with [`Error::Never`]
+    /// unless the parser [snaps gaps](Parser::snap_gaps). A fixed duration never lands in a gap.
+    ///
+    /// Defaults to `false`, where a month is 30.44 days and a year is 365.25 days.
+    pub fn months_as_calendar(mut self, enabled: bool) -> Self {
+        self.months_as_calendar = enabled;
+        self
+    }
+
+    /// Accept a `'T'` separating the date and time, as in ISO 8601 (e.g. `"2018-08-20T09:11:12"`).
+    ///
+    /// Defaults to `false`.
+    pub fn accept_iso_t(mut self, enabled: bool) -> Self {
+        self.accept_iso_t = enabled;
+        self
+    }
+
+    /// Accept [RFC 3339] times which carry their own offset (e.g. `"2018-08-20T09:11:12+02:00"`).
+    ///
+    /// An RFC 3339 time is interpreted in its own offset before being converted into the specified timezone,
+    /// so it is never ambiguous.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [RFC 3339]: https://www.rfc-editor.org/rfc/rfc3339
+    pub fn accept_rfc3339(mut self, enabled: bool) -> Self {
+        self.accept_rfc3339 = enabled;
+        self
+    }
+
+    /// The time of day assumed when a date is given without a time,
+    /// either as a strftime date (e.g. `"2018-08-20"`) or as one of the keywords `"today"`, `"yesterday"` and `"tomorrow"`.
+    ///
+    /// Defaults to midnight, 00:00:00. End of day semantics may be had with `23:59:59.999999`.
+    pub fn default_time(mut self, time: NaiveTime) -> Self {
+        self.default_time = time;
+        self
+    }
+
+    /// Resolve ambiguous times into a [`LocalDateTime::Single`] with the given strategy.
+    ///
+    /// By default ambiguous times are returned as [`LocalDateTime::Ambiguous`], leaving the choice to the caller.
+    pub fn resolve_ambiguous(mut self, strategy: AmbiguousStrategy) -> Self {
+        self.ambiguous = Some(strategy);
+        self
+    }
+
+    /// Snap a time which is skipped by the timezone, such as during a daylight saving time spring-forward,
+    /// to the first time following the gap.
+    ///
+    /// For example, when clocks spring forward from 02:00 to 03:00, `"02:30"` is snapped to 03:00.
+    /// This also applies to dates whose midnight, or default time, falls in a gap.
+    ///
+    /// Defaults to `false`, where such a time is rejected with [`Error::Never`].
+    pub fn snap_gaps(mut self, enabled: bool) -> Self {
+        self.snap_gaps = enabled;
+        self
+    }
+
+    /// Match keywords (e.g. `"Today"`, `"NOW"`, `"3s AGO"`) and time units (e.g. `"1H"`) regardless of case.
+    ///
+    /// The time units `"M"` and `"m"` still match exactly, so they remain months and minutes respectively.
+    ///
+    /// Defaults to `false`, where keywords and time units must be given in the case documented.
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Truncate the current time to a whole number of the given unit, e.g. to whole seconds for stable log correlation.
+    ///
+    /// The truncation applies to every time relative to now, such as `"now"`, `"+1h"` and `"3s ago"`,
+    /// including a `now` given to [`Parser::parse_tz_at`]. Units are truncated in UTC.
+    ///
+    /// Defaults to no truncation, where the current time has nanosecond precision.
+    pub fn truncate_now_to(mut self, unit: TimeUnit) -> Self {
+        self.truncate_now_to = Some(unit);
+        self
+    }
+
+    /// Reject a number of a time span which is split by whitespace, e.g. `"1 1s"`, rather than joining its digits.
+    ///
+    /// Defaults to `false`, where whitespace is removed from a time span so that `"1 1s"` is `"11s"`.
+    pub fn reject_split_numbers(mut self, enabled: bool) -> Self {
+        self.reject_split_numbers = enabled;
+        self
+    }
+
+    /// Accept dates in the given order in addition to the year, month, day order.
+    ///
+    /// With [`DateOrder::DayMonthYear`], a date such as `"20.08.2018"` may be followed by a time of hours and minutes,
+    /// with optional seconds (e.g. `"20.08.2018 09:11:12"`), but not by fractional seconds.
+    /// Such dates are tried before a `'.'` is taken to separate fractional seconds.
+    ///
+    /// Defaults to [`DateOrder::YearMonthDay`].
+    pub fn date_format(mut self, order: DateOrder) -> Self {
+        self.date_format = order;
+        self
+    }
+
+    /// Accept times on a twelve-hour clock followed by `AM` or `PM` in any case (e.g. `"09:11 PM"`, `"09:11:12pm"`),
+    /// optionally preceded by a date (e.g. `"2018-08-20 09:11 PM"`).
+    ///
+    /// The hour must be from 1 to 12, so `"13:00 PM"` is rejected.
+    ///
+    /// Defaults to `false`.
+    pub fn accept_am_pm(mut self, enabled: bool) -> Self {
+        self.accept_am_pm = enabled;
+        self
+    }
+
+    /// Accept a time followed by a numeric timezone offset of hours and minutes (e.g. `"2018-08-20 09:00 +0200"`),
+    /// in which case the time is interpreted in that offset before being converted into the given timezone.
+    ///
+    /// An offset is a `'+'` or `'-'` followed by exactly four digits and no time unit, so `"09:00 +0200"` is a time
+    /// in UTC+2 whereas `"09:00 +02m"` is two minutes after 09:00. A time span of a bare four digit number
+    /// would be rejected for lacking a time unit, so the two never collide.
+    ///
+    /// Defaults to `false`.
+    pub fn accept_numeric_zone(mut self, enabled: bool) -> Self {
+        self.accept_numeric_zone = enabled;
+        self
+    }
+
+    /// Define a custom time unit of the given duration (e.g. `"sprint"` of 14 days) for use in time spans.
+    ///
+    /// The built-in time units take precedence, so a custom time unit cannot redefine one.
+    /// A custom time unit is always a fixed duration, even one as long as a month with [calendar months](Parser::months_as_calendar).
+    /// A custom time unit is matched exactly, unless the parser is [`case_insensitive`](Parser::case_insensitive),
+    /// in which case it is also matched in lowercase.
+    ///
+    /// # Panics
+    /// Panics if the name is not made up of alphabetic characters, or if the duration is not positive
+    /// or does not fit in an i64 of nanoseconds.
+    pub fn add_unit(mut self, name: &str, duration: Duration) -> Self {
+        assert!(
+            !name.is_empty() && name.chars().all(char::is_alphabetic),
+            "time unit `{name}` must be made up of alphabetic characters"
+        );
+        self.units.insert(name.to_owned(), unit_len(name, duration));
+        self
+    }
+
+    /// Set the duration of a month (e.g. 30 days), used when months are not [calendar months](Parser::months_as_calendar).
+    ///
+    /// Defaults to 30.44 days, as with systemd.
+    ///
+    /// # Panics
+    /// Panics if the duration is not positive or does not fit in an i64 of nanoseconds.
+    pub fn month_len(mut self, duration: Duration) -> Self {
+        self.month_len = Some(unit_len("month", duration));
+        self
+    }
+
+    /// Set the duration of a year (e.g. 360 days), used when years are not [calendar years](Parser::months_as_calendar).
+    ///
+    /// Defaults to 365.25 days, as with systemd.
+    ///
+    /// # Panics
+    /// Panics if the duration is not positive or does not fit in an i64 of nanoseconds.
+    pub fn year_len(mut self, duration: Duration) -> Self {
+        self.year_len = Some(unit_len("year", duration));
+        self
+    }
+
+    /// Parse a timestamp made up solely of digits as that many of the given time unit from now
+    /// (e.g. `"30"` as `"+30s"` with a unit of `"s"`).
+    ///
+    /// Eight digits remain a compact date (e.g. `"20180820"`), and `"@30"` remains 30 seconds after the epoch.
+    /// The unit is looked up when parsing, so it may be a custom time unit (see [`add_unit`](Parser::add_unit)).
+    ///
+    /// Defaults to `None`, where a bare number is rejected for lacking a time unit.
+    pub fn bare_number_unit(mut self, unit: Option<&str>) -> Self {
+        self.bare_number_unit = unit.map(str::to_owned);
+        self
+    }
+
+    /// Accept an `"a"` or `"an"` in place of the number one before a time unit
+    /// (e.g. `"a day ago"`, `"in an hour"`, `"today + a week"`).
+    ///
+    /// The article must be a word of its own, so `"ams"` remains an unknown time unit,
+    /// and must not follow a number, so `"1 a day"` is rejected rather than read as `"11 days"`.
+    ///
+    /// Defaults to `false`.
+    pub fn accept_articles(mut self, enabled: bool) -> Self {
+        self.accept_articles = enabled;
+        self
+    }
+
+    /// Ignore a single `'.'` or `','` ending a timestamp, as when a timestamp ends a sentence
+    /// (e.g. `"tomorrow."` in `"due by tomorrow."`).
+    ///
+    /// Only a mark ending the timestamp is ignored, so a fraction such as that of `"09:11:12.5"` is still parsed,
+    /// while `"09:11:12."` is the same as `"09:11:12"` rather than a fraction lacking its digits.
+    ///
+    /// Defaults to `false`.
+    pub fn trim_trailing_punct(mut self, enabled: bool) -> Self {
+        self.trim_trailing_punct = enabled;
+        self
+    }
+
+    /// Accept a date whose day is beyond the end of its month, rather than rejecting it.
+    ///
+    /// A day beyond the end of its month is clamped to the last day of the month (e.g. `"2018-02-30"` is
+    /// 2018-02-28), and may be followed by a time. A month, or a day beyond 31, is still rejected,
+    /// as are hours beyond 23, minutes beyond 59 and seconds beyond 60 (e.g. `"2018-08-20 25:00"`).
+    ///
+    /// Defaults to `false`.
+    pub fn lenient_fields(mut self, enabled: bool) -> Self {
+        self.lenient_fields = enabled;
+        self
+    }
+
+    /// Accept a timestamp relative to now, such as `"now + 1h"`, `"today"`, `"09:00"` or `"3s ago"`.
+    ///
+    /// When disabled, a timestamp whose time depends on now is rejected with [`Error::Format`], so that a timestamp
+    /// read from a configuration file, say, resolves to the same time whenever it is parsed. Whether a time depends
+    /// on now is determined as with [`Parser::parse_meta_tz_at`], so `"2018-08-20"` and `"epoch + 1h"` are accepted.
+    ///
+    /// Defaults to `true`.
+    pub fn allow_relative(mut self, enabled: bool) -> Self {
+        self.allow_relative = enabled;
+        self
+    }
+
+    /// Require a space before the `+` or `-` of an offset following a time, or following another offset.
+    ///
+    /// When disabled, a sign directly after a time or time span also begins an offset (e.g. `"today+1h-30m"`).
+    /// The time and its offsets must still both parse, so the `-` separating the fields of a date
+    /// such as `"2018-08-20-1d"` is not mistaken for an offset.
+    ///
+    /// Defaults to `true`, where such a timestamp is rejected.
+    pub fn require_space_before_sign(mut self, enabled: bool) -> Self {
+        self.require_space_before_sign = enabled;
+        self
+    }
+
+    /// Anchor a time given without a date to the given date, rather than to the date of now.
+    #[cfg(feature = "std")]
+    pub(crate) fn anchor_date(mut self, date: NaiveDate) -> Self {
+        self.anchor_date = Some(date);
+        self
+    }
+
+    /// Parse a timestamp returning a `DateTime` with the specified timezone.
+    #[cfg(feature = "std")]
+    pub fn parse_tz<S, T, Tz>(&self, timestamp: S, timezone: T) -> Result<LocalDateTime<Tz>, Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        // sample the clock once so that every relative component of the timestamp agrees on now
+        self.parse_tz_at(timestamp, timezone, Utc::now())
+    }
+
+    /// Parse a timestamp returning a `DateTime` with the specified timezone,
+    /// resolving relative times against the given `now` instead of the system clock.
+    pub fn parse_tz_at<S, T, Tz>(
+        &self,
+        timestamp: S,
+        timezone: T,
+        now: DateTime<Utc>,
+    ) -> Result<LocalDateTime<Tz>, Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let (ts, tz) = (timestamp.as_ref(), timezone.borrow());
+        let time = self.parse_resolved(ts, tz, now)?;
+        if !self.allow_relative && self.resolution(ts, tz, now, &time) == Resolution::RelativeToNow
+        {
+            return Err(Error::Format(
+                "Timestamp is relative to now, but only absolute timestamps are allowed".to_owned(),
+            )
+            .with_input(ts));
+        }
+        Ok(time)
+    }
+
+    /// Parse a timestamp, resolving an ambiguous time with the configured strategy.
+    fn parse_resolved<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        let time = self
+            .parse_timestamp(ts, tz, now)
+            .map_err(|e| e.with_input(ts))?;
+        match (time, self.ambiguous) {
+            (LocalDateTime::Ambiguous(dt, _), Some(AmbiguousStrategy::Earliest)) => {
+                Ok(LocalDateTime::Single(dt))
+            }
+            (LocalDateTime::Ambiguous(_, dt), Some(AmbiguousStrategy::Latest)) => {
+                Ok(LocalDateTime::Single(dt))
+            }
+            (time, Some(AmbiguousStrategy::Reject)) => {
+                time.into_single().map(LocalDateTime::Single)
+            }
+            (time, _) => Ok(time),
+        }
+    }
+
+    /// Parse a timestamp as with [`parse_tz_at`](Parser::parse_tz_at),
+    /// also returning whether the time is relative to `now`.
+    ///
+    /// A timestamp is relative when its time depends on `now`, which is determined by also resolving it
+    /// against a second instant more than a day away, so that `"today"` and `"09:00"` are relative too.
+    pub fn parse_meta_tz_at<S, T, Tz>(
+        &self,
+        timestamp: S,
+        timezone: T,
+        now: DateTime<Utc>,
+    ) -> Result<(LocalDateTime<Tz>, Resolution), Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let (ts, tz) = (timestamp.as_ref(), timezone.borrow());
+        let time = self.parse_tz_at::<_, _, Tz>(ts, tz, now)?;
+        let resolution = self.resolution(ts, tz, now, &time);
+        Ok((time, resolution))
+    }
+
+    /// Whether the time of a timestamp depends on now, by also resolving it against a second instant
+    /// more than a day away.
+    ///
+    /// * `time` - the time of the timestamp resolved against `now`.
+    fn resolution<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: DateTime<Utc>,
+        time: &LocalDateTime<Tz>,
+    ) -> Resolution {
+        let shift = Duration::days(1) + Duration::seconds(1);
+        let other_now = now
+            .checked_sub_signed(shift)
+            .or_else(|| now.checked_add_signed(shift))
+            .unwrap_or(now);
+        // a time which cannot be resolved against another instant, such as for a weekday, depends on now
+        match self.parse_resolved(ts, tz, other_now) {
+            Ok(other) if other == *time => Resolution::Absolute,
+            _ => Resolution::RelativeToNow,
+        }
+    }
+
+    /// Parse a timestamp with the specified timezone returning the signed duration from now until it,
+    /// which is negative for a timestamp in the past.
+    ///
+    /// The clock is sampled once, so `"now + 2h"` is exactly two hours from now. A time which is ambiguous in
+    /// the timezone is resolved as configured by [`Parser::resolve_ambiguous`], or to its earliest possible
+    /// conversion time when no strategy is configured.
+    #[cfg(feature = "std")]
+    pub fn parse_until_tz<S, T, Tz>(&self, timestamp: S, timezone: T) -> Result<Duration, Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let now = Utc::now();
+        let time = self.parse_tz_at(timestamp, timezone, now)?;
+        Ok(time.earliest().signed_duration_since(now))
+    }
+
+    /// Parse the longest timestamp at the start of the input, returning it along with the unparsed remainder.
+    ///
+    /// The timestamp must end at the end of the input or before whitespace, so the remainder is either empty or
+    /// starts with whitespace. An error is returned when no prefix of the input is a valid timestamp.
+    #[cfg(feature = "std")]
+    pub fn parse_prefix_tz<'a, T, Tz>(
+        &self,
+        input: &'a str,
+        timezone: T,
+    ) -> Result<(LocalDateTime<Tz>, &'a str), Error>
+    where
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let tz = timezone.borrow();
+        // sample the clock once so that every candidate prefix agrees on now
+        let now = Utc::now();
+
+        // each candidate prefix ends before a run of whitespace, or at the end of the input
+        let mut ends = input
+            .char_indices()
+            .zip(input.chars().skip(1))
+            .filter(|&((_, c), next)| !c.is_whitespace() && next.is_whitespace())
+            .map(|((p, c), _)| p + c.len_utf8())
+            .collect::<Vec<_>>();
+        ends.push(input.len());
+
+        let mut error = None;
+        for &end in ends.iter().rev() {
+            match self.parse_tz_at(&input[..end], tz, now) {
+                Ok(time) => return Ok((time, &input[end..])),
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        Err(error.unwrap())
+    }
+
+    /// Parse a list of timestamps separated by `", "` (e.g. `"now, today + 1h, epoch"`),
+    /// returning each in order.
+    ///
+    /// A comma not followed by a space, such as that of fractional seconds in `"09:11:12,123"`, does not separate.
+    /// The clock is sampled once, so relative times agree across the list.
+    /// The first invalid timestamp is rejected with [`Error::List`], holding its index and error.
+    #[cfg(feature = "std")]
+    pub fn parse_list_tz<S, T, Tz>(
+        &self,
+        list: S,
+        timezone: T,
+    ) -> Result<Vec<LocalDateTime<Tz>>, Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let tz = timezone.borrow();
+        let now = Utc::now();
+        list.as_ref()
+            .split(", ")
+            .enumerate()
+            .map(|(index, item)| {
+                self.parse_tz_at(item.trim(), tz, now)
+                    .map_err(|e| Error::List {
+                        index,
+                        source: Box::new(e),
+                    })
+            })
+            .collect()
+    }
+
+    /// Parse a range of two timestamps separated by `" .. "` (e.g. `"yesterday .. today"`),
+    /// returning the start and end of the range.
+    ///
+    /// The clock is sampled once, so relative times agree across both ends of the range.
+    /// A range whose start is after its end is rejected with [`Error::Format`].
+    #[cfg(feature = "std")]
+    pub fn parse_range_tz<S, T, Tz>(
+        &self,
+        range: S,
+        timezone: T,
+    ) -> Result<(LocalDateTime<Tz>, LocalDateTime<Tz>), Error>
+    where
+        S: AsRef<str>,
+        T: Borrow<Tz>,
+        Tz: TimeZone,
+    {
+        let range = range.as_ref();
+        let tz = timezone.borrow();
+        let now = Utc::now();
+
+        // the separator is surrounded by whitespace so as not to collide with fractional seconds
+        let (start, end) = range.split_once(" .. ").ok_or_else(|| {
+            Error::Format(format!(
+                "Range `{range}` must be two timestamps separated by ` .. `"
+            ))
+        })?;
+        let start = self.parse_tz_at(start.trim(), tz, now)?;
+        let end = self.parse_tz_at(end.trim(), tz, now)?;
+        if start > end {
+            return Err(Error::Format(format!(
+                "Range `{range}` cannot start after it ends"
+            )));
+        }
+        Ok((start, end))
+    }
+
+    /// Parse a timestamp into a `DateTime` with the given timezone.
+    ///
+    /// * `ts` - a str of a timestamp with whitespace intact.
+    /// * `tz` - the time zone to use.
+    /// * `now` - the instant that relative times are resolved against.
+    fn parse_timestamp<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        let now = match self.truncate_now_to {
+            Some(unit) => now.duration_trunc(unit.duration()).map_err(|e| {
+                Error::Overflow(format!("Truncating `{now}` to a {unit:?} overflowed: {e}"))
+            })?,
+            None => now,
+        };
+
+        // a single trailing punctuation mark, as ends a sentence, is not part of the timestamp
+        let ts = match self.trim_trailing_punct {
+            true => ts.strip_suffix(['.', ',']).unwrap_or(ts),
+            false => ts,
+        };
+
+        if ts.trim().is_empty() {
+            return Err(Error::Format("Timestamp cannot be empty".to_owned()));
+        }
+
+        // Fast Path - an exact keyword, such as `"now"` parsed in a hot loop, is a time on its own,
+        // so neither needs its whitespace stripped nor searched for offsets
+        if let Some(time) = self.parse_keyword(ts, tz, &now)? {
+            return Ok(time);
+        }
+
+        /*
+         * A timestamp is composed of two parts: a time and an offset relative to that time.
+         *
+         * In the general case, the time is separated from the offset by either a '+' or '-'
+         * character which denotes how the offset is relative to that time.
+         *
+         * There are a few special cases which are not handled by the general case.
+         * These are detected, and handled, before applying the general case algorithm.
+         */
+
+        // Special Case 1 - a suffix of " left" or " ago", or a prefix of '+' or '-':
+        //  - the time is now.
+        //  - the offset consists of the remaining characters added to or subtracted from the current time, respectively.
+        if self.is_offset_from_now(ts) {
+            let now = LocalDateTime::Single(now.with_timezone(tz));
+            let offset = self.parse_signed_offset(ts)?;
+            return offset.apply(now, self);
+        }
+
+        // Special Case 2 - a prefix of '@':
+        //  - the time is the unix epoch.
+        //  - the offset consists of the remaining characters added to the epoch time,
+        //    where a bare number without a time unit is a count of seconds and fractional seconds.
+        if ts.starts_with('@') {
+            let epoch = LocalDateTime::Single(tz.timestamp_opt(0, 0).unwrap());
+            let ts_nw = self.strip_span(ts)?;
+            // the '@' is an ascii character, so slicing by byte is safe
+            let span = &ts_nw[1..];
+            if !span.is_empty() && span.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                let duration = parse_epoch_seconds(span)?;
+                return epoch.checked_add(duration).ok_or_else(|| {
+                    Error::Overflow(format!("Adding `{span}` seconds to the epoch overflowed"))
+                });
+            }
+            let offset = self
+                .parse_offset(span)
+                .map_err(|e| e.map_position(|at| unstripped_position(ts, at + 1)))?;
+            return offset.apply(epoch, self);
+        }
+
+        // Special Case 3 - a bare number, when configured with a time unit for it:
+        //  - the time is now.
+        //  - the offset is the number of that time unit added to the current time.
+        if let Some(unit) = &self.bare_number_unit {
+            if ts.bytes().all(|b| b.is_ascii_digit()) && !is_compact_date(ts) {
+                let now = LocalDateTime::Single(now.with_timezone(tz));
+                let offset = self.parse_offset(&format!("{ts}{unit}"))?;
+                return offset.apply(now, self);
+            }
+        }
+
+        // General Case - the time is separated from the offsets by either a '+' or '-'.
+        self.parse_general(ts, tz, &now).map(|(_, time)| time)
+    }
+
+    /// Whether a timestamp is an offset relative to now, without a time of its own,
+    /// as it is prefixed with a '+', '-' or "in ", or suffixed with " left", " ago" or " from now".
+    pub(crate) fn is_offset_from_now(&self, ts: &str) -> bool {
+        (ts.starts_with(['+', '-'])
+            && !ts.get(..9).is_some_and(|p| self.is_keyword(p, "-infinity")))
+            || self.starts_with_keyword(ts, "in ")
+            || self.ends_with_keyword(ts, " left")
+            || self.ends_with_keyword(ts, " ago")
+            || self.ends_with_keyword(ts, " from now")
+    }
+
+    /// Parse a timestamp of a time followed by offsets separated from it by either a '+' or '-',
+    /// returning the position of the sign beginning the offsets, if any, along with the time.
+    ///
+    /// The time may itself contain a sign, such as a numeric timezone offset, so the offsets are
+    /// the longest trailing run of signed time spans preceded by a time, both of which must fully parse.
+    /// When no split parses, the error of the split at the first sign is reported.
+    ///
+    /// * `ts` - a str of a timestamp with whitespace intact.
+    /// * `tz` - the time zone to use.
+    /// * `now` - the instant that relative times are resolved against.
+    pub(crate) fn parse_general<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Result<(Option<usize>, LocalDateTime<Tz>), Error> {
+        let mut first_err = None;
+        let mut start = 0;
+        loop {
+            let split = self.find_sign(&ts[start..]).map(|p| start + p);
+            match self.parse_split(ts, split, tz, now) {
+                Ok(time) => return Ok((split, time)),
+                Err(e @ (Error::Format(_) | Error::Number { .. } | Error::TimeUnit { .. })) => {
+                    first_err.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+            match split {
+                Some(p) => start = p + 1,
+                None => return Err(first_err.unwrap()),
+            }
+        }
+    }
+
+    /// Parse a timestamp split into a time and the offsets following it.
+    ///
+    /// * `ts` - a str of a timestamp with whitespace intact.
+    /// * `split` - the position of the sign beginning the offsets, if any.
+    /// * `tz` - the time zone to use.
+    /// * `now` - the instant that relative times are resolved against.
+    fn parse_split<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        split: Option<usize>,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        let Some(p) = split else {
+            return self.parse_time(ts, tz, now);
+        };
+        // the space before the sign separates the time from its offsets, rather than being part of the time
+        let time = ts[..p].strip_suffix(' ').unwrap_or(&ts[..p]);
+        let time = self.parse_time(time, tz, now)?;
+        let offset = self
+            .parse_offsets(&ts[p..])
+            .map_err(|e| e.map_position(|at| at + p))?;
+        // an infinite time saturates, so is not moved by its offsets
+        match &time {
+            LocalDateTime::Single(dt)
+                if *dt == infinity(tz, true) || *dt == infinity(tz, false) =>
+            {
+                Ok(time)
+            }
+            _ => offset.apply(time, self),
+        }
+    }
+
+    /// Parse a keyword which is a time on its own, returning `None` for any other str.
+    ///
+    /// * `keyword` - a str matched exactly against the keywords.
+    /// * `tz` - the time zone to use.
+    /// * `now` - the instant that relative times are resolved against.
+    fn parse_keyword<Tz: TimeZone>(
+        &self,
+        keyword: &str,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Result<Option<LocalDateTime<Tz>>, Error> {
+        let dt = match keyword {
+            "now" => LocalDateTime::Single(now.with_timezone(tz)),
+            "epoch" => LocalDateTime::Single(tz.timestamp_opt(0, 0).unwrap()),
+            "infinity" => LocalDateTime::Single(infinity(tz, true)),
+            "-infinity" => LocalDateTime::Single(infinity(tz, false)),
+            "today" => self.at_default_time(naive_today(tz, now), tz)?,
+            "yesterday" => self.at_default_time(naive_today(tz, now) - Days::new(1), tz)?,
+            "tomorrow" => self.at_default_time(naive_today(tz, now) + Days::new(1), tz)?,
+            "midnight" => {
+                self.local_datetime(naive_today(tz, now).and_hms_opt(0, 0, 0).unwrap(), tz)?
+            }
+            "noon" => {
+                self.local_datetime(naive_today(tz, now).and_hms_opt(12, 0, 0).unwrap(), tz)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(dt))
+    }
+
+    /// Parse a point-in-time into a `DateTime` with the given timezone.
+    ///
+    /// * `ts` - a str of a time with whitespace intact.
+    /// * `tz` - the time zone to use.
+    /// * `now` - the instant that relative times are resolved against.
+    pub(crate) fn parse_time<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        if self.accept_rfc3339 {
+            if let Ok(dt) = DateTime::parse_from_rfc3339(ts) {
+                return Ok(LocalDateTime::Single(dt.with_timezone(tz)));
+            }
+        }
+
+        // an optional "UTC" or "local" suffix, or numeric offset suffix,
+        // interprets the time in that timezone before converting it
+        if self.accept_numeric_zone {
+            // the whitespace may be a multi-byte character, so the zone starts after its full width
+            if let Some((p, zone)) = ts
+                .char_indices()
+                .rev()
+                .find(|&(_, c)| c.is_whitespace())
+                .map(|(p, c)| (p, &ts[(p + c.len_utf8())..]))
+                .filter(|&(_, zone)| is_numeric_zone(zone))
+            {
+                let offset = parse_numeric_zone(zone)?;
+                return Ok(self
+                    .parse_time(ts[..p].trim_end(), &offset, now)?
+                    .map(|dt| dt.with_timezone(tz)));
+            }
+        }
+        if let Some(ts_utc) = self.strip_keyword_suffix(ts, " UTC") {
+            return Ok(self
+                .parse_time(ts_utc.trim_end(), &Utc, now)?
+                .map(|dt| dt.with_timezone(tz)));
+        }
+        #[cfg(feature = "std")]
+        if let Some(ts_local) = self.strip_keyword_suffix(ts, " local") {
+            return Ok(self
+                .parse_time(ts_local.trim_end(), &chrono::Local, now)?
+                .map(|dt| dt.with_timezone(tz)));
+        }
+
+        // an optional weekday prefix asserts the weekday of the time which follows it
+        let (weekday, ts) = match ts.split_once(' ') {
+            Some((prefix, ts_tail)) => match prefix.parse::<Weekday>() {
+                Ok(weekday) => (Some(weekday), ts_tail.trim_start()),
+                Err(_) => (None, ts),
+            },
+            None => (None, ts),
+        };
+
+        let ts_iso = if self.accept_iso_t {
+            replace_iso_t(ts)
+        } else {
+            Cow::Borrowed(ts)
+        };
+        let ts: &str = &ts_iso;
+
+        // a keyword is a time on its own, so anything following it is a mistake rather than part of a strftime time
+        if let Some((word, ts_tail)) = ts.split_once(char::is_whitespace) {
+            let ts_tail = ts_tail.trim();
+            if !ts_tail.is_empty() && KEYWORDS.iter().any(|k| self.is_keyword(word, k)) {
+                return Err(Error::Format(format!(
+                    "Unexpected `{ts_tail}` after the keyword `{word}`, which is a time on its own"
+                )));
+            }
+        }
+
+        let keyword = if self.case_insensitive {
+            Cow::Owned(ts.to_lowercase())
+        } else {
+            Cow::Borrowed(ts)
+        };
+        let dt = match self.parse_keyword(&keyword, tz, now)? {
+            Some(dt) => dt,
+            None => match self
+                .parse_dotted_date(ts)
+                .or_else(|| self.parse_am_pm(ts, tz, now))
+            {
+                Some(ndt) => self.local_datetime(ndt, tz)?,
+                None => match ts.find(['.', ',']) {
+                    // an optional '.' or ',' separates the seconds and fractional seconds components,
+                    // which chrono reads positionally as a fraction of a second
+                    Some(p) => {
+                        // whitespace would otherwise leave chrono to misread the fractional seconds,
+                        // which are only mistaken for such when they follow the seconds of a time
+                        let spaced = ts[..p].ends_with(char::is_whitespace)
+                            || ts[(p + 1)..].starts_with(char::is_whitespace);
+                        let after_seconds = ts[..p]
+                            .split_whitespace()
+                            .next_back()
+                            .is_some_and(|t| NaiveTime::parse_from_str(t, "%H:%M:%S").is_ok());
+                        if spaced && after_seconds {
+                            return Err(Error::Number {
+                                msg: format!(
+                                    "Fractional seconds are separated from the seconds by whitespace in `{ts}`"
+                                ),
+                                source: None,
+                            });
+                        }
+                        let ts_f = match ts.as_bytes()[p] {
+                            b',' => Cow::Owned(ts.replacen(',', ".", 1)),
+                            _ => Cow::Borrowed(ts),
+                        };
+                        let ndt = NaiveDateTime::parse_from_str(&ts_f, "%y-%m-%d %H:%M:%S%.f")
+                            .or_else(|_| {
+                                NaiveDateTime::parse_from_str(&ts_f, "%Y-%m-%d %H:%M:%S%.f")
+                            })
+                            .or_else(|_| {
+                                NaiveTime::parse_from_str(&ts_f, "%H:%M:%S%.f")
+                                    .map(|nt| self.date_of_time(tz, now).and_time(nt))
+                            })
+                            .map_err(|_| {
+                                Error::Format(format!(
+                                    "Cannot parse `{ts}` into a time with fractional seconds"
+                                ))
+                            })?;
+                        self.local_datetime(ndt, tz)?
+                    }
+                    None => NaiveDateTime::parse_from_str(ts, "%y-%m-%d %H:%M:%S")
+                        .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S"))
+                        .or_else(|_| NaiveDateTime::parse_from_str(ts, "%y-%m-%d %H:%M"))
+                        .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M"))
+                        .or_else(|_| {
+                            NaiveDate::parse_from_str(ts, "%y-%m-%d")
+                                .map(|nd| nd.and_time(self.default_time))
+                        })
+                        .or_else(|_| {
+                            NaiveDate::parse_from_str(ts, "%Y-%m-%d")
+                                .map(|nd| nd.and_time(self.default_time))
+                        })
+                        .or_else(|e| match is_compact_date(ts) {
+                            true => NaiveDate::parse_from_str(ts, "%Y%m%d")
+                                .map(|nd| nd.and_time(self.default_time)),
+                            false => Err(e),
+                        })
+                        .or_else(|_| {
+                            NaiveTime::parse_from_str(ts, "%H:%M:%S")
+                                .map(|nt| self.date_of_time(tz, now).and_time(nt))
+                        })
+                        .or_else(|_| {
+                            NaiveTime::parse_from_str(ts, "%H:%M")
+                                .map(|nt| self.date_of_time(tz, now).and_time(nt))
+                        })
+                        .or_else(|e| self.parse_lenient(ts, tz, now).ok_or(e))
+                        .map_err(|_| Error::Format(format!("Cannot parse `{ts}` into a time")))
+                        .and_then(|ndt| self.local_datetime(ndt, tz))?,
+                },
+            },
+        };
+
+        if let Some(weekday) = weekday {
+            let (LocalDateTime::Single(d) | LocalDateTime::Ambiguous(d, _)) = &dt;
+            if d.weekday() != weekday {
+                return Err(Error::Format(format!(
+                    "Weekday `{weekday}` does not match `{ts}`, which is a {}",
+                    d.weekday()
+                )));
+            }
+        }
+        Ok(dt)
+    }
+
+    /// Parse a date and time whose fields are out of range, when configured to.
+    ///
+    /// The date is `%Y-%m-%d` or `%y-%m-%d`, and the time `%H:%M:%S` or `%H:%M`, either of which may be omitted.
+    /// A day beyond the end of its month is clamped to its last day, while each time field must be within its range,
+    /// where a second of 60 is a leap second.
+    fn parse_lenient<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Option<NaiveDateTime> {
+        if !self.lenient_fields {
+            return None;
+        }
+
+        let field = |s: &str, max_len: usize| {
+            (!s.is_empty() && s.len() <= max_len && s.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| s.parse::<u32>().ok())
+                .flatten()
+        };
+        let (date, time) = match ts.split_once(' ') {
+            Some((date, time)) => (Some(date), Some(time)),
+            None if ts.contains(':') => (None, Some(ts)),
+            None => (Some(ts), None),
+        };
+
+        let date = match date {
+            Some(date) => {
+                let mut fields = date.split('-');
+                let (year, month, day) = (fields.next()?, fields.next()?, fields.next()?);
+                if fields.next().is_some() {
+                    return None;
+                }
+                // as with chrono's `%y`, a two digit year is from 1969 to 2068
+                let year = match (year.len(), field(year, 4)? as i32) {
+                    (2, year @ 0..=68) => 2000 + year,
+                    (2, year) => 1900 + year,
+                    (4, year) => year,
+                    _ => return None,
+                };
+                let (month, day) = (field(month, 2)?, field(day, 2)?);
+                if !(1..=31).contains(&day) {
+                    return None;
+                }
+                let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+                first.with_day(day.min(last.day()))?
+            }
+            None => self.date_of_time(tz, now),
+        };
+
+        let Some(time) = time else {
+            return Some(date.and_time(self.default_time));
+        };
+        let mut fields = time.split(':');
+        let (hours, minutes) = (field(fields.next()?, 2)?, field(fields.next()?, 2)?);
+        let seconds = fields.next().map_or(Some(0), |seconds| field(seconds, 2))?;
+        if fields.next().is_some() {
+            return None;
+        }
+        // chrono represents a leap second as the 59th second with an extra second of nanoseconds
+        let time = match seconds {
+            60 => NaiveTime::from_hms_nano_opt(hours, minutes, 59, 1_000_000_000)?,
+            _ => NaiveTime::from_hms_opt(hours, minutes, seconds)?,
+        };
+        Some(date.and_time(time))
+    }
+
+    /// Parse a date in day, month, year order separated by '.', with an optional time, when configured to.
+    fn parse_dotted_date(&self, ts: &str) -> Option<NaiveDateTime> {
+        if self.date_format != DateOrder::DayMonthYear {
+            return None;
+        }
+        NaiveDateTime::parse_from_str(ts, "%d.%m.%Y %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(ts, "%d.%m.%Y %H:%M"))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(ts, "%d.%m.%Y").map(|nd| nd.and_time(self.default_time))
+            })
+            .ok()
+    }
+
+    /// Parse a time on a twelve-hour clock followed by AM or PM, with an optional date, when configured to.
+    fn parse_am_pm<Tz: TimeZone>(
+        &self,
+        ts: &str,
+        tz: &Tz,
+        now: &DateTime<Utc>,
+    ) -> Option<NaiveDateTime> {
+        let is_am_pm = ts.get(ts.len().saturating_sub(2)..).is_some_and(|suffix| {
+            suffix.eq_ignore_ascii_case("am") || suffix.eq_ignore_ascii_case("pm")
+        });
+        if !self.accept_am_pm || !is_am_pm {
+            return None;
+        }
+        NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %I:%M:%S %p")
+            .or_else(|_| NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %I:%M %p"))
+            .or_else(|_| {
+                NaiveTime::parse_from_str(ts, "%I:%M:%S %p")
+                    .map(|nt| self.date_of_time(tz, now).and_time(nt))
+            })
+            .or_else(|_| {
+                NaiveTime::parse_from_str(ts, "%I:%M %p")
+                    .map(|nt| self.date_of_time(tz, now).and_time(nt))
+            })
+            .ok()
+    }
+
+    /// Whether the str is the given keyword, ignoring case when configured to.
+    fn is_keyword(&self, s: &str, keyword: &str) -> bool {
+        match self.case_insensitive {
+            true => s.eq_ignore_ascii_case(keyword),
+            false => s == keyword,
+        }
+    }
+
+    /// Whether the str starts with the given keyword, ignoring case when configured to.
+    fn starts_with_keyword(&self, s: &str, keyword: &str) -> bool {
+        s.get(..keyword.len())
+            .is_some_and(|prefix| self.is_keyword(prefix, keyword))
+    }
+
+    /// Whether the str ends with the given keyword, ignoring case when configured to.
+    fn ends_with_keyword(&self, s: &str, keyword: &str) -> bool {
+        s.len()
+            .checked_sub(keyword.len())
+            .and_then(|p| s.get(p..))
+            .is_some_and(|suffix| self.is_keyword(suffix, keyword))
+    }
+
+    /// The str preceding the given keyword suffix, when the str ends with it.
+    fn strip_keyword_suffix<'a>(&self, s: &'a str, keyword: &str) -> Option<&'a str> {
+        self.ends_with_keyword(s, keyword)
+            .then(|| &s[..(s.len() - keyword.len())])
+    }
+
+    /// The date of a time given without one, which is the anchor date when set, or otherwise the date of now.
+    fn date_of_time<Tz: TimeZone>(&self, tz: &Tz, now: &DateTime<Utc>) -> NaiveDate {
+        self.anchor_date.unwrap_or_else(|| naive_today(tz, now))
+    }
+
+    /// Convert a local time into a `DateTime` with the given timezone,
+    /// snapping a time skipped by the timezone forward when enabled.
+    fn local_datetime<Tz: TimeZone>(
+        &self,
+        datetime: NaiveDateTime,
+        tz: &Tz,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        match LocalDateTime::from_datetime(datetime, tz) {
+            Err(Error::Never(time)) if self.snap_gaps => LocalDateTime::after_gap(datetime, tz)
+                .map(LocalDateTime::Single)
+                .ok_or(Error::Never(time)),
+            res => res,
+        }
+    }
+
+    /// Convert a date without a time into a `DateTime` at the default time of day.
+    fn at_default_time<Tz: TimeZone>(
+        &self,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        self.local_datetime(date.and_time(self.default_time), tz)
+    }
+
+    /// Parse an offset relative to now into a single span, either prefixed with a '+', '-' or "in ",
+    /// or suffixed with " left", " ago" or " from now".
+    ///
+    /// * `ts` - a str of an offset with whitespace intact.
+    pub(crate) fn parse_signed_offset(&self, ts: &str) -> Result<Span, Error> {
+        if ts.starts_with(['+', '-']) {
+            return self.parse_offsets(ts);
+        }
+        if self.starts_with_keyword(ts, "in ") {
+            // the prefix is ascii, so slicing by byte is safe
+            let span = &ts[3..];
+            let span_nw = self.strip_span(span)?;
+            if span_nw.is_empty() {
+                return Err(Error::Format(
+                    "Offset after `in` cannot be empty".to_owned(),
+                ));
+            }
+            return self
+                .parse_offset(&span_nw)
+                .map_err(|e| e.map_position(|at| 3 + unstripped_position(span, at)));
+        }
+        // each suffix is ascii, so slicing it off by byte is safe
+        if self.ends_with_keyword(ts, " from now") {
+            let ts_nw = self.strip_span(ts)?;
+            return self
+                .parse_nonempty_offset(&ts_nw[..(ts_nw.len() - 7)], "from now")
+                .map_err(|e| e.map_position(|at| unstripped_position(ts, at)));
+        }
+        if self.ends_with_keyword(ts, " left") {
+            let ts_nw = self.strip_span(ts)?;
+            return self
+                .parse_nonempty_offset(&ts_nw[..(ts_nw.len() - 4)], "left")
+                .map_err(|e| e.map_position(|at| unstripped_position(ts, at)));
+        }
+        if self.ends_with_keyword(ts, " ago") {
+            let ts_nw = self.strip_span(ts)?;
+            return self
+                .parse_nonempty_offset(&ts_nw[..(ts_nw.len() - 3)], "ago")
+                .map(Span::neg)
+                .map_err(|e| e.map_position(|at| unstripped_position(ts, at)));
+        }
+        Err(Error::Format(format!(
+            "Offset `{ts}` must be prefixed with a `+`, `-` or `in `, or suffixed with ` left`, ` ago` or ` from now`"
+        )))
+    }
+
+    /// Parse the time spans preceding a " left", " ago" or " from now" suffix, which cannot be empty.
+    fn parse_nonempty_offset(&self, ts_nw: &str, suffix: &str) -> Result<Span, Error> {
+        if ts_nw.is_empty() {
+            return Err(Error::Format(format!(
+                "Offset before `{suffix}` cannot be empty"
+            )));
+        }
+        self.parse_offset(ts_nw)
+    }
+
+    /// Parse and combine a chain of signed offsets into a single span.
+    ///
+    /// Each offset is a time span prefixed with either a '+' or '-',
+    /// which adds or subtracts the time span from the total respectively.
+    ///
+    /// * `ts` - a str of offsets with whitespace intact, where every offset but the first is preceded by a space,
+    ///   unless a space is not required before a sign.
+    fn parse_offsets(&self, ts: &str) -> Result<Span, Error> {
+        let mut total = Span::default();
+        let mut ts = ts;
+        // the position of the current offset in the str of offsets
+        let mut pos = 0;
+        loop {
+            // the sign is an ascii character, so slicing by byte is safe
+            let (sign, ts_tail) = ts.split_at(1);
+            let (span, ts_next) = match self.find_sign(ts_tail) {
+                Some(p) => (&ts_tail[..p], Some(&ts_tail[p..])),
+                None => (ts_tail, None),
+            };
+
+            let span_nw = self.strip_span(span)?;
+            if span_nw.is_empty() {
+                return Err(Error::Format(match ts_next {
+                    Some(_) => "Timestamp cannot contain consecutive `+` or `-` signs".to_owned(),
+                    None => format!("Offset after `{sign}` cannot be empty"),
+                }));
+            }
+
+            let offset = self
+                .parse_offset(&span_nw)
+                .map_err(|e| e.map_position(|at| pos + 1 + unstripped_position(span, at)))?;
+            let offset = match sign {
+                "+" => offset,
+                _ => offset.neg(),
+            };
+            total = total.checked_add(offset).ok_or_else(|| {
+                Error::Overflow(format!(
+                    "Combining offset `{sign}{span_nw}` with the preceding offsets overflowed"
+                ))
+            })?;
+
+            match ts_next {
+                Some(ts_next) => {
+                    pos += ts.len() - ts_next.len();
+                    ts = ts_next;
+                }
+                None => return Ok(total),
+            }
+        }
+    }
+
+    /// The position of the first sign which may begin an offset, which is one preceded by a space,
+    /// or one preceded by any character when a space is not required before a sign.
+    fn find_sign(&self, ts: &str) -> Option<usize> {
+        if self.require_space_before_sign {
+            return find_sign(ts).map(|p| p + 1);
+        }
+        ts.char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '+' || c == '-')
+            .map(|(p, _)| p)
+    }
+
+    /// Remove all whitespace from time spans, first checking that no number is split by whitespace when configured to.
+    ///
+    /// * `ts` - a str of time spans with whitespace intact.
+    fn strip_span<'a>(&self, ts: &'a str) -> Result<Cow<'a, str>, Error> {
+        if self.reject_split_numbers {
+            let is_number = |c: char| c.is_ascii_digit() || c == '.';
+            let mut prev = None;
+            let mut split = false;
+            for c in ts.chars() {
+                if c.is_whitespace() {
+                    split = prev.is_some();
+                    continue;
+                }
+                if split && prev.is_some_and(is_number) && is_number(c) {
+                    return Err(Error::Number {
+                        msg: format!("Number is split by whitespace in `{}`", ts.trim()),
+                        source: None,
+                    });
+                }
+                prev = Some(c);
+                split = false;
+            }
+        }
+        if self.accept_articles {
+            return Ok(Cow::Owned(self.strip_articles(ts)));
+        }
+        Ok(strip_whitespace(ts))
+    }
+
+    /// Remove all whitespace from time spans, replacing each `"a"` or `"an"` preceding a time unit with the number one.
+    ///
+    /// An article is replaced by a number of the same length, `"1"` or `"01"` respectively,
+    /// so that positions in the stripped str still map back to the original str.
+    ///
+    /// * `ts` - a str of time spans with whitespace intact.
+    fn strip_articles(&self, ts: &str) -> String {
+        let mut stripped = String::with_capacity(ts.len());
+        let mut words = ts.split_whitespace().peekable();
+        let mut after_number = false;
+        while let Some(word) = words.next() {
+            let before_unit = words
+                .peek()
+                .is_some_and(|next| next.starts_with(char::is_alphabetic));
+            let is_article = !after_number && before_unit;
+            if is_article && self.is_keyword(word, "a") {
+                stripped.push('1');
+            } else if is_article && self.is_keyword(word, "an") {
+                stripped.push_str("01");
+            } else {
+                stripped.push_str(word);
+            }
+            after_number = word.ends_with(|c: char| c.is_ascii_digit() || c == '.');
+        }
+        stripped
+    }
+
+    /// The nanosecond weight of a built-in or custom time unit, along with whether it is built-in,
+    /// or `None` when the time unit is not understood.
+    ///
+    /// Only built-in days, weeks, months and years are calendar units, whatever the weight of a custom time unit.
+    fn unit_to_nsec(&self, unit: &str) -> Option<(i64, bool)> {
+        match unit_to_nsec(unit) {
+            Some(nsecs) => Some((nsecs, true)),
+            None => self.units.get(unit).map(|&nsecs| (nsecs, false)),
+        }
+    }
+
+    /// Parse and combine all time spans into a single span.
+    ///
+    /// Whitespace between a number and its time unit, or between time spans, is skipped in place,
+    /// so that a time span is parsed without allocating.
+    ///
+    /// * `ts_nw` - a str of time spans with whitespace removed, or with whitespace only between components.
+    pub(crate) fn parse_offset(&self, mut ts_nw: &str) -> Result<Span, Error> {
+        // an ISO 8601 duration cannot be mistaken for time spans, which start with a number
+        if ts_nw.starts_with('P') {
+            return self.parse_offset(&iso_duration_to_spans(ts_nw)?);
+        }
+
+        let len = ts_nw.len();
+        let mut total_months: i64 = 0;
+        let mut total_days: i64 = 0;
+        let mut total_nsecs: i128 = 0;
+        loop {
+            if ts_nw.is_empty() {
+                return Ok(Span {
+                    months: total_months,
+                    days: total_days,
+                    nsecs: total_nsecs,
+                });
+            }
+
+            /*
+             * Time spans have the format: "<number><multipler>"
+             */
+
+            // time spans after the first may be signed (e.g. "3h-30m"), in which case the sign applies to just that span
+            let (negative, ts_unsigned) = match ts_nw.as_bytes()[0] {
+                b'+' if ts_nw.len() < len => (false, &ts_nw[1..]),
+                b'-' if ts_nw.len() < len => (true, &ts_nw[1..]),
+                _ => (false, ts_nw),
+            };
+
+            // look for digit characters to make up the `number`, optionally followed by a '.' and fractional digits,
+            // followed by alphabetic characters to make up the `multiplier`
+            let (digits, ts_tail) = partition_predicate(ts_unsigned, |c| c.is_ascii_digit());
+            let (fraction, ts_tail) = match ts_tail.strip_prefix('.') {
+                Some(ts_tail) => {
+                    let (fraction, ts_tail) = partition_predicate(ts_tail, |c| c.is_ascii_digit());
+                    if fraction.is_empty() || ts_tail.starts_with('.') {
+                        return Err(Error::Number {
+                            msg: format!(
+                                "Cannot parse `{digits}.{fraction}` into a decimal number"
+                            ),
+                            source: None,
+                        });
+                    }
+                    (fraction, ts_tail)
+                }
+                None => ("", ts_tail),
+            };
+            let ts_tail = ts_tail.trim_start();
+            let at = len - ts_tail.len();
+            let (letters, ts_tail) = partition_predicate(ts_tail, char::is_alphabetic);
+            ts_nw = ts_tail.trim_start();
+
+            // parse the `number` and `multipler` strings into i64
+            let number: i64 = digits.parse().map_err(|e: ParseIntError| match e.kind() {
+                IntErrorKind::PosOverflow => {
+                    Error::Overflow(format!("Cannot parse `{digits}` into a number: {e}"))
+                }
+                _ => Error::Number {
+                    msg: format!("Cannot parse `{digits}` into a number"),
+                    source: Some(e),
+                },
+            })?;
+            // an exact match comes first, so that `M` and `m` remain months and minutes respectively
+            let multiplier = self.unit_to_nsec(letters).or_else(|| {
+                self.case_insensitive
+                    .then(|| self.unit_to_nsec(&letters.to_lowercase()))
+                    .flatten()
+            });
+            let Some((multiplier, built_in)) = multiplier else {
+                // the input is attached once the position is relative to it
+                return Err(Error::TimeUnit {
+                    unit: letters.to_owned(),
+                    at,
+                    input: String::new(),
+                });
+            };
+
+            // increment the total calendar months when months and years are not fixed durations
+            if self.months_as_calendar
+                && built_in
+                && (multiplier == NSEC_PER_MONTH || multiplier == NSEC_PER_YEAR)
+            {
+                if !fraction.is_empty() {
+                    return Err(Error::Number {
+                        msg: format!(
+                            "Cannot apply `{digits}.{fraction}{letters}` as a whole number of calendar months"
+                        ),
+                        source: None,
+                    });
+                }
+
+                let months_per_unit = if multiplier == NSEC_PER_YEAR { 12 } else { 1 };
+                total_months = number
+                    .checked_mul(months_per_unit)
+                    .map(|months| if negative { -months } else { months })
+                    .and_then(|months| months.checked_add(total_months))
+                    .ok_or_else(|| {
+                        Error::Overflow(format!(
+                            "Offset months overflowed: total_months `{total_months}` number `{number}` unit `{letters}`"
+                        ))
+                    })?;
+                continue;
+            }
+
+            // increment the total calendar days when whole days and weeks are not fixed durations
+            if self.months_as_calendar
+                && built_in
+                && fraction.is_empty()
+                && (multiplier == NSEC_PER_DAY || multiplier == NSEC_PER_WEEK)
+            {
+                let days_per_unit = if multiplier == NSEC_PER_WEEK { 7 } else { 1 };
+                total_days = number
+                    .checked_mul(days_per_unit)
+                    .map(|days| if negative { -days } else { days })
+                    .and_then(|days| days.checked_add(total_days))
+                    .ok_or_else(|| {
+                        Error::Overflow(format!(
+                            "Offset days overflowed: total_days `{total_days}` number `{number}` unit `{letters}`"
+                        ))
+                    })?;
+                continue;
+            }
+
+            let multiplier = match multiplier {
+                NSEC_PER_MONTH if built_in => self.month_len.unwrap_or(multiplier),
+                NSEC_PER_YEAR if built_in => self.year_len.unwrap_or(multiplier),
+                _ => multiplier,
+            };
+
+            // increment the total nanosecond offset, which is only range checked once the span is applied
+            total_nsecs = (i128::from(number) * i128::from(multiplier))
+                .checked_add(fraction_nsecs(fraction, multiplier))
+                .map(|nsecs| if negative { -nsecs } else { nsecs })
+                .and_then(|nsecs| nsecs.checked_add(total_nsecs))
+                .ok_or_else(|| {
+                    Error::Overflow(format!(
+                        "Offset nanoseconds overflowed: total_nsecs `{total_nsecs}` number `{number}` multiplier `{multiplier}`"
+                    ))
+                })?;
+        }
+    }
+}
+
+/// A time span split into calendar months, calendar days and an exact number of nanoseconds.
+///
+/// The nanoseconds are accumulated in an i128, so that large spans of opposite signs may cancel out,
+/// and are only checked to fit in a `Duration` when the span is converted into one.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Span {
+    pub(crate) months: i64,
+    pub(crate) days: i64,
+    pub(crate) nsecs: i128,
+}
+
+impl Span {
+    fn neg(self) -> Self {
+        Span {
+            months: -self.months,
+            days: -self.days,
+            nsecs: -self.nsecs,
+        }
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Some(Span {
+            months: self.months.checked_add(rhs.months)?,
+            days: self.days.checked_add(rhs.days)?,
+            nsecs: self.nsecs.checked_add(rhs.nsecs)?,
+        })
+    }
+
+    /// The exact part of the span as a `Duration`, failing when its magnitude exceeds `MAX_SPAN`.
+    pub(crate) fn duration(self) -> Result<Duration, Error> {
+        if self.nsecs.unsigned_abs() > MAX_SPAN_NSECS.unsigned_abs() {
+            return Err(Error::Overflow(format!(
+                "Offset nanoseconds `{}` exceed the maximum time span of `{MAX_SPAN}`",
+                self.nsecs
+            )));
+        }
+        // the magnitude is at most i64::MAX microseconds, so the conversions cannot fail
+        let usecs = (self.nsecs / i128::from(NSEC_PER_USEC)) as i64;
+        let nsecs = (self.nsecs % i128::from(NSEC_PER_USEC)) as i64;
+        Ok(Duration::microseconds(usecs) + Duration::nanoseconds(nsecs))
+    }
+
+    /// Apply the span to a time, first adding the calendar months, then the calendar days and then the duration.
+    pub(crate) fn apply<Tz: TimeZone>(
+        self,
+        time: LocalDateTime<Tz>,
+        parser: &Parser,
+    ) -> Result<LocalDateTime<Tz>, Error> {
+        let duration = self.duration()?;
+        let add_duration = |time: LocalDateTime<Tz>| {
+            let ndt = time.clone().earliest().naive_local();
+            time.checked_add(duration).ok_or_else(|| {
+                Error::Overflow(format!("Adding `{duration}` to `{ndt}` overflowed"))
+            })
+        };
+        if self.months == 0 && self.days == 0 {
+            return add_duration(time);
+        }
+
+        // the candidates of an ambiguous time share the same local time, so the earliest is used
+        let dt = time.earliest();
+        let tz = dt.timezone();
+        let ndt = dt.naive_local();
+
+        let (months, days) = (self.months, self.days);
+        let overflow = || {
+            Error::Overflow(format!(
+                "Adding `{months}` months and `{days}` days to `{ndt}` overflowed"
+            ))
+        };
+        let abs_months = u32::try_from(months.unsigned_abs()).map_err(|_| overflow())?;
+        let ndt = match months {
+            0.. => ndt.checked_add_months(Months::new(abs_months)),
+            _ => ndt.checked_sub_months(Months::new(abs_months)),
+        }
+        .and_then(|ndt| match days {
+            0.. => ndt.checked_add_days(Days::new(days.unsigned_abs())),
+            _ => ndt.checked_sub_days(Days::new(days.unsigned_abs())),
+        })
+        .ok_or_else(overflow)?;
+        add_duration(parser.local_datetime(ndt, &tz)?)
+    }
+}
+
+/// The nanosecond weight of a time unit of the given duration.
+///
+/// # Panics
+/// Panics if the duration is not positive or does not fit in an i64 of nanoseconds.
+fn unit_len(name: &str, duration: Duration) -> i64 {
+    duration
+        .num_nanoseconds()
+        .filter(|&nsecs| nsecs > 0)
+        .unwrap_or_else(|| {
+            panic!("time unit `{name}` must be positive and fit in an i64 of nanoseconds")
+        })
+}
+
+/// The latest instant whose local time is representable in the timezone, or the earliest if not `future`.
+///
+/// The extreme UTC instants are moved in by the offset of the timezone,
+/// where their local time would otherwise fall outside the range of a `NaiveDateTime`.
+fn infinity<Tz: TimeZone>(tz: &Tz, future: bool) -> DateTime<Tz> {
+    let utc = match future {
+        true => NaiveDateTime::MAX,
+        false => NaiveDateTime::MIN,
+    };
+    let offset = tz.offset_from_utc_datetime(&utc).fix().local_minus_utc();
+    let offset = Duration::seconds(offset.into());
+    let utc = match future {
+        true => utc - offset.max(Duration::zero()),
+        false => utc - offset.min(Duration::zero()),
+    };
+    tz.from_utc_datetime(&utc)
+}
+
+/// Whether a str is a numeric timezone offset of a '+' or '-' followed by exactly four digits.
+fn is_numeric_zone(ts: &str) -> bool {
+    let bytes = ts.as_bytes();
+    bytes.len() == 5 && matches!(bytes[0], b'+' | b'-') && bytes[1..].iter().all(u8::is_ascii_digit)
+}
+
+/// Parse a numeric timezone offset of hours and minutes, e.g. "+0200".
+fn parse_numeric_zone(zone: &str) -> Result<FixedOffset, Error> {
+    // the offset is made up of ascii characters, so slicing by byte is safe
+    let hours: i32 = zone[1..3].parse().unwrap();
+    let minutes: i32 = zone[3..].parse().unwrap();
+    let secs = hours * 3600 + minutes * 60;
+    let offset = match (minutes < 60, &zone[..1]) {
+        (true, "+") => FixedOffset::east_opt(secs),
+        (true, _) => FixedOffset::west_opt(secs),
+        (false, _) => None,
+    };
+    offset.ok_or_else(|| Error::Format(format!("Cannot parse `{zone}` into a timezone offset")))
+}
+
+/// Whether a str may be a compact `%Y%m%d` date, which requires exactly eight digits
+/// so that longer or shorter numbers are never read as a date.
+fn is_compact_date(ts: &str) -> bool {
+    ts.len() == 8 && ts.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Parse a bare number of seconds since the epoch, with an optional fractional seconds component.
+///
+/// As with a time's fractional seconds, the fraction is read positionally
+/// and digits beyond nanosecond precision are truncated.
+///
+/// * `span` - a non-empty str of ascii digits and '.' characters.
+fn parse_epoch_seconds(span: &str) -> Result<Duration, Error> {
+    let (secs, fraction) = span.split_once('.').unwrap_or((span, ""));
+    if fraction.contains('.') || span.ends_with('.') {
+        return Err(Error::Number {
+            msg: format!("Cannot parse `{span}` into a number of seconds"),
+            source: None,
+        });
+    }
+
+    let overflow = || Error::Overflow(format!("Cannot parse `{span}` into a number of seconds"));
+    let secs: i64 = match secs {
+        "" => 0,
+        // all characters are ascii digits, so the only possible error is overflow
+        _ => secs.parse().map_err(|_| overflow())?,
+    };
+    // all characters are ascii digits, so slicing by byte is safe
+    let digits = &fraction[..fraction.len().min(9)];
+    let nsecs = match digits {
+        "" => 0,
+        _ => digits.parse::<i64>().unwrap() * 10_i64.pow(9 - digits.len() as u32),
+    };
+    Duration::try_seconds(secs)
+        .and_then(|duration| duration.checked_add(&Duration::nanoseconds(nsecs)))
+        .ok_or_else(overflow)
+}
+
+/// Translate an ISO 8601 duration (e.g. `"P1DT2H30M"`) into the equivalent time spans (e.g. `"1d2h30min"`).
+///
+/// Each designator must follow those before it in the order `Y`, `M`, `W`, `D`, `T`, `H`, `M`, `S`,
+/// where the `'T'` separates the months `M` of the date from the minutes `M` of the time.
+/// A number may include a fractional part, separated by a `'.'` or `','`.
+///
+/// * `iso` - a str of an ISO 8601 duration, starting with a `'P'`, with whitespace removed.
+fn iso_duration_to_spans(iso: &str) -> Result<String, Error> {
+    const DESIGNATORS: [(char, &str); 7] = [
+        ('Y', "y"),
+        ('M', "M"),
+        ('W', "w"),
+        ('D', "d"),
+        ('H', "h"),
+        ('M', "min"),
+        ('S', "s"),
+    ];
+    let malformed =
+        |reason: &str| Error::Format(format!("Cannot parse ISO 8601 duration `{iso}`: {reason}"));
+
+    let mut spans = String::new();
+    // the index of the next designator which may follow, where the time designators start at `H`
+    let mut next = 0;
+    let mut in_time = false;
+    let mut ts = &iso[1..];
+    while !ts.is_empty() {
+        if let Some(ts_tail) = ts.strip_prefix('T') {
+            if in_time {
+                return Err(malformed("`T` must appear at most once"));
+            }
+            if ts_tail.is_empty() {
+                return Err(malformed("`T` must be followed by a time component"));
+            }
+            (in_time, next, ts) = (true, 4, ts_tail);
+            continue;
+        }
+
+        let (number, ts_tail) =
+            partition_predicate(ts, |c| c.is_ascii_digit() || c == '.' || c == ',');
+        if number.is_empty() {
+            return Err(malformed("a designator must be preceded by a number"));
+        }
+        let mut chars = ts_tail.chars();
+        let designator = chars
+            .next()
+            .ok_or_else(|| malformed("a number must be followed by a designator"))?;
+        let end = if in_time { DESIGNATORS.len() } else { 4 };
+        let p = (next..end)
+            .find(|&p| DESIGNATORS[p].0 == designator)
+            .ok_or_else(|| malformed(&format!("unexpected designator `{designator}`")))?;
+        spans.push_str(&number.replace(',', "."));
+        spans.push_str(DESIGNATORS[p].1);
+        next = p + 1;
+        ts = chars.as_str();
+    }
+    if spans.is_empty() {
+        return Err(malformed("a duration must have at least one component"));
+    }
+    Ok(spans)
+}
+
+/// Replace a 'T' separating the date and time components with a space.
+fn replace_iso_t(ts: &str) -> Cow<'_, str> {
+    let bytes = ts.as_bytes();
+    let separator = ts.find('T').filter(|&p| {
+        p > 0 && bytes[p - 1].is_ascii_digit() && bytes.get(p + 1).is_some_and(u8::is_ascii_digit)
+    });
+
+    match separator {
+        // the separator is an ascii character, so slicing by byte is safe
+        Some(p) => Cow::Owned(format!("{} {}", &ts[..p], &ts[(p + 1)..])),
+        None => Cow::Borrowed(ts),
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Days, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use proptest::prelude::*;
+
+use crate::{
+    NSEC_PER_DAY, NSEC_PER_HOUR, NSEC_PER_MINUTE, NSEC_PER_MONTH, NSEC_PER_MSEC, NSEC_PER_NSEC,
+    NSEC_PER_SEC, NSEC_PER_USEC, NSEC_PER_WEEK, NSEC_PER_YEAR,
+};
+
+/// A proptest `Strategy` generating timestamps along with the `DateTime` in UTC they parse into,
+/// when parsed by [`parse_timestamp_tz_at`](crate::parse_timestamp_tz_at) with the given `now`.
+///
+/// The timestamps span the time keywords, strftime formats, the `'@'` prefix and chains of offsets.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{parse_timestamp_tz_at, timestamp_strategy};
+/// use chrono::{TimeZone, Utc};
+/// use proptest::proptest;
+///
+/// let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+/// proptest!(|((timestamp, dt) in timestamp_strategy(now))| {
+///     assert_eq!(parse_timestamp_tz_at(&timestamp, Utc, now).unwrap().single(), Some(dt));
+/// });
+/// ```
+pub fn timestamp_strategy(now: DateTime<Utc>) -> impl Strategy<Value = (String, DateTime<Utc>)> {
+    let with_offsets = (
+        time_strategy(now),
+        prop::collection::vec(offset_strategy(), 0..3),
+    )
+        .prop_map(|((mut timestamp, mut dt), offsets)| {
+            for (offset, duration) in offsets {
+                timestamp.push(' ');
+                timestamp.push_str(&offset);
+                dt += duration;
+            }
+            (timestamp, dt)
+        });
+
+    let only_offsets = prop::collection::vec(offset_strategy(), 1..3).prop_map(move |offsets| {
+        let timestamp = offsets
+            .iter()
+            .map(|(offset, _)| offset.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let dt = offsets.iter().fold(now, |dt, (_, duration)| dt + *duration);
+        (timestamp, dt)
+    });
+
+    let epoch_seconds = (0_i64..4_102_444_800)
+        .prop_map(|secs| (format!("@{secs}"), Utc.timestamp_opt(secs, 0).unwrap()));
+
+    prop_oneof![6 => with_offsets, 2 => only_offsets, 1 => epoch_seconds]
+}
+
+/// Generate a time along with the `DateTime` in UTC it parses into.
+fn time_strategy(now: DateTime<Utc>) -> impl Strategy<Value = (String, DateTime<Utc>)> {
+    let today = now.date_naive();
+    let keyword = prop_oneof![
+        Just(("now", now)),
+        Just(("epoch", DateTime::UNIX_EPOCH)),
+        Just(("today", today.and_time(NaiveTime::MIN).and_utc())),
+        Just((
+            "yesterday",
+            (today - Days::new(1)).and_time(NaiveTime::MIN).and_utc()
+        )),
+        Just((
+            "tomorrow",
+            (today + Days::new(1)).and_time(NaiveTime::MIN).and_utc()
+        )),
+        Just(("midnight", today.and_time(NaiveTime::MIN).and_utc())),
+        Just(("noon", today.and_hms_opt(12, 0, 0).unwrap().and_utc())),
+    ]
+    .prop_map(|(keyword, dt)| (keyword.to_owned(), dt));
+
+    let date = (1970_i32..2069, 1_u32..=12, 1_u32..=28)
+        .prop_map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap());
+    let time = (0_u32..24, 0_u32..60, 0_u32..60, 0_u32..1_000_000)
+        .prop_map(|(h, m, s, us)| NaiveTime::from_hms_micro_opt(h, m, s, us).unwrap());
+    let strftime = (date, time, 0_usize..7).prop_map(move |(date, time, format)| {
+        let (format, time) = match format {
+            0 => ("%Y-%m-%d %H:%M:%S%.6f", time),
+            1 => ("%y-%m-%d %H:%M:%S%.6f", time),
+            2 => ("%Y-%m-%d %H:%M:%S", time.with_nanosecond(0).unwrap()),
+            3 => (
+                "%y-%m-%d %H:%M",
+                time.with_second(0).unwrap().with_nanosecond(0).unwrap(),
+            ),
+            4 => ("%Y-%m-%d", NaiveTime::MIN),
+            5 => ("%H:%M:%S", time.with_nanosecond(0).unwrap()),
+            _ => (
+                "%H:%M",
+                time.with_second(0).unwrap().with_nanosecond(0).unwrap(),
+            ),
+        };
+        // a time without a date is today
+        let date = match format.starts_with("%H") {
+            true => today,
+            false => date,
+        };
+        let dt = date.and_time(time);
+        (dt.format(format).to_string(), dt.and_utc())
+    });
+
+    prop_oneof![keyword, strftime]
+}
+
+/// Generate a signed offset along with the `Duration` it adds to a time.
+fn offset_strategy() -> impl Strategy<Value = (String, Duration)> {
+    #[rustfmt::skip]
+    const UNITS: [(&str, i64); 16] = [
+        ("ns", NSEC_PER_NSEC),
+        ("us", NSEC_PER_USEC),
+        ("ms", NSEC_PER_MSEC),
+        ("s", NSEC_PER_SEC),
+        ("sec", NSEC_PER_SEC),
+        ("m", NSEC_PER_MINUTE),
+        ("min", NSEC_PER_MINUTE),
+        ("h", NSEC_PER_HOUR),
+        ("hours", NSEC_PER_HOUR),
+        ("d", NSEC_PER_DAY),
+        ("days", NSEC_PER_DAY),
+        ("w", NSEC_PER_WEEK),
+        ("M", NSEC_PER_MONTH),
+        ("months", NSEC_PER_MONTH),
+        ("y", NSEC_PER_YEAR),
+        ("years", NSEC_PER_YEAR),
+    ];
+
+    let component =
+        (0_i64..100, 0..UNITS.len(), any::<bool>()).prop_map(|(number, unit, space)| {
+            let (unit, multiplier) = UNITS[unit];
+            let space = if space { " " } else { "" };
+            (
+                format!("{number}{space}{unit}"),
+                Duration::nanoseconds(number * multiplier),
+            )
+        });
+
+    (
+        any::<bool>(),
+        any::<bool>(),
+        prop::collection::vec(component, 1..4),
+    )
+        .prop_map(|(negative, space, components)| {
+            let span = components
+                .iter()
+                .map(|(component, _)| component.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let duration = components
+                .iter()
+                .fold(Duration::zero(), |total, (_, duration)| total + *duration);
+
+            let sign = if negative { "-" } else { "+" };
+            let space = if space { " " } else { "" };
+            let duration = if negative { -duration } else { duration };
+            (format!("{sign}{space}{span}"), duration)
+        })
+}
@@ -0,0 +1,95 @@
+use std::ops::Deref;
+use std::str::FromStr;
+
+use chrono::offset::{Local, Utc};
+use chrono::{DateTime, TimeZone};
+
+use crate::{parse_timestamp, parse_timestamp_utc, parse_to_utc, Error, LocalDateTime};
+
+/// A timestamp parsed with [`str::parse`].
+///
+/// Parsing is implemented for the [`Utc`] and [`Local`] timezones.
+/// Timestamps in any other timezone must be parsed with [`parse_timestamp_tz`].
+///
+/// [`parse_timestamp_tz`]: crate::parse_timestamp_tz
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::{LocalDateTime, SystemdTimestamp};
+/// use chrono::{TimeZone, Utc};
+///
+/// let timestamp: SystemdTimestamp<Utc> = "epoch +1529578800s".parse().unwrap();
+/// assert_eq!(timestamp.0,
+///            LocalDateTime::Single(Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap()));
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct SystemdTimestamp<Tz: TimeZone>(pub LocalDateTime<Tz>);
+
+impl FromStr for SystemdTimestamp<Utc> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_timestamp_utc(s).map(SystemdTimestamp)
+    }
+}
+
+impl FromStr for SystemdTimestamp<Local> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_timestamp(s).map(SystemdTimestamp)
+    }
+}
+
+impl<Tz: TimeZone> From<SystemdTimestamp<Tz>> for LocalDateTime<Tz> {
+    fn from(timestamp: SystemdTimestamp<Tz>) -> Self {
+        timestamp.0
+    }
+}
+
+/// A timestamp converted into a single instant in UTC with [`TryFrom`].
+///
+/// The timestamp is parsed as with [`parse_to_utc`](crate::parse_to_utc) in [`Utc`],
+/// and dereferences to the `DateTime<Utc>` it holds.
+///
+/// # Examples
+/// ```rust
+/// # use chrono_systemd_time::UtcTimestamp;
+/// use chrono::{TimeZone, Utc};
+///
+/// let timestamp = UtcTimestamp::try_from("@1529578800").unwrap();
+/// assert_eq!(*timestamp, Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap());
+/// assert_eq!(timestamp.timestamp(), 1_529_578_800);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtcTimestamp(pub DateTime<Utc>);
+
+impl TryFrom<&str> for UtcTimestamp {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        parse_to_utc(s, Utc).map(UtcTimestamp)
+    }
+}
+
+impl TryFrom<String> for UtcTimestamp {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::try_from(s.as_str())
+    }
+}
+
+impl Deref for UtcTimestamp {
+    type Target = DateTime<Utc>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<UtcTimestamp> for DateTime<Utc> {
+    fn from(timestamp: UtcTimestamp) -> Self {
+        timestamp.0
+    }
+}
@@ -1,10 +1,43 @@
-use chrono::offset::{Local, Utc};
-use chrono::{DateTime, Duration, NaiveTime, TimeZone};
+use chrono::offset::{FixedOffset, Local, LocalResult, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::America::New_York;
+use chrono_tz::US::Eastern;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::HashSet;
 
+use super::add_calendar_span;
+use super::duration_components;
+use super::format_duration;
+use super::is_valid_timestamp;
 use super::naive_today;
+use super::parse_duration;
+use super::parse_duration_in_place;
+use super::parse_list_tz;
+use super::parse_offset_signed;
+use super::parse_range_tz;
+use super::parse_std_duration;
+use super::parse_time_on_date_tz;
+use super::parse_time_tz;
+use super::parse_time_until_tz;
+use super::parse_timestamp;
+use super::parse_timestamp_prefix_tz;
+use super::parse_timestamp_rfc3339_tz;
 use super::parse_timestamp_tz;
+use super::parse_timestamp_tz_at;
+use super::parse_timestamp_tz_bytes;
+use super::parse_timestamp_tz_meta;
+use super::parse_timestamp_tz_with_clock;
+use super::parse_timestamp_utc;
+use super::parse_to_utc;
 use super::Error;
-use super::{USEC_PER_MONTH, USEC_PER_YEAR};
+use super::{parse_many_iter_tz, parse_many_tz};
+use super::{time_keywords, time_units, tokenize};
+use super::{unit_to_nsec, MAX_SPAN, NSEC_PER_MONTH, NSEC_PER_YEAR, TIME_UNITS};
+use super::{
+    AmbiguousStrategy, Clock, DateOrder, DurationComponents, LocalDateTime, Parser, Resolution,
+    SystemClock, SystemdTimestamp, TimeUnit, Token, UtcTimestamp,
+};
 
 /*
  * Positive Tests
@@ -26,6 +59,11 @@ fn time_word() {
     assert_eq!(parse_timestamp_tz_aux("today", Utc), today_utc);
     assert_eq!(parse_timestamp_tz_aux("tomorrow", Utc), tomorrow_utc);
     assert_eq!(parse_timestamp_tz_aux("yesterday", Utc), yesterday_utc);
+    assert_eq!(parse_timestamp_tz_aux("midnight", Utc), today_utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("noon", Utc),
+        parse_timestamp_tz_aux("today + 12h", Utc)
+    );
 
     let today_local = today_time(&Local, None);
     let tomorrow_local = today_local + Duration::days(1);
@@ -35,6 +73,259 @@ fn time_word() {
     assert_eq!(parse_timestamp_tz_aux("yesterday", Local), yesterday_local);
 }
 
+/// Test a keyword followed by more input is rejected with a precise error.
+#[test]
+fn time_word_extra() {
+    assert_eq!(
+        parse_timestamp_tz("today today", Utc),
+        Err(Error::Format(
+            "Unexpected `today` after the keyword `today`, which is a time on its own (in \"today today\")"
+                .to_owned()
+        ))
+    );
+    assert_eq!(
+        parse_timestamp_tz("now epoch", Utc),
+        Err(Error::Format(
+            "Unexpected `epoch` after the keyword `now`, which is a time on its own (in \"now epoch\")"
+                .to_owned()
+        ))
+    );
+    for ts in ["tomorrow 09:00", "today  today + 1h", "noon\tnow"] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Format(msg)) if msg.starts_with("Unexpected")),
+            "{ts}"
+        );
+    }
+    assert!(matches!(
+        Parser::new().case_insensitive(true).parse_tz("Today TODAY", Utc),
+        Err(Error::Format(msg)) if msg.starts_with("Unexpected `TODAY` after the keyword `Today`")
+    ));
+
+    // suffixes and prefixes of a keyword are still accepted
+    assert!(parse_timestamp_tz("today UTC", Utc).is_ok());
+    assert!(parse_timestamp_tz("today + 1h", Utc).is_ok());
+}
+
+/// Test parsing into the local timezone and UTC.
+#[test]
+fn time_local_utc() {
+    assert_eq!(
+        parse_timestamp("2018-08-09 07:06:05").unwrap().single(),
+        parse_timestamp_tz("2018-08-09 07:06:05", Local)
+            .unwrap()
+            .single()
+    );
+    assert_eq!(
+        parse_timestamp("epoch + 1h").unwrap().single().unwrap(),
+        Utc.timestamp_opt(3600, 0).unwrap()
+    );
+
+    assert_eq!(
+        parse_timestamp_utc("2018-08-09 07:06:05").unwrap().single(),
+        Some(Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap())
+    );
+    assert_eq!(
+        parse_timestamp_utc("today").unwrap().single(),
+        Some(today_time(&Utc, None))
+    );
+    assert!(matches!(
+        parse_timestamp_utc("+5"),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test every string type and timezone form is accepted without conversion.
+#[test]
+fn time_input_forms() {
+    let expected = Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap();
+    let owned = String::from("2018-08-20 09:11:12");
+    let boxed: Box<str> = owned.clone().into_boxed_str();
+    assert_eq!(parse_timestamp_tz_aux(&owned, Utc), expected);
+    assert_eq!(
+        parse_timestamp_tz(&owned, Utc).unwrap().single(),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_timestamp_tz(&boxed, Utc).unwrap().single(),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_timestamp_tz(boxed.clone(), Utc).unwrap().single(),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_timestamp_tz(owned.clone(), Utc).unwrap().single(),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_timestamp(&owned).unwrap().single(),
+        Some(expected.with_timezone(&Local))
+    );
+    assert!(is_valid_timestamp(&boxed));
+    assert_eq!(
+        parse_duration(Box::<str>::from("1h")),
+        Ok(Duration::hours(1))
+    );
+    let span = String::from("1h");
+    assert_eq!(parse_duration(&span), Ok(Duration::hours(1)));
+    assert_eq!(parse_duration(span), Ok(Duration::hours(1)));
+
+    // a timezone is accepted by value or by reference, as from a function given a borrowed timezone
+    fn parse_by_reference<Tz: TimeZone>(ts: &str, tz: &Tz) -> LocalDateTime<Tz> {
+        let now = Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap();
+        parse_timestamp_tz_at(ts, tz, now).unwrap()
+    }
+    let now = Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at("09:11:12", Eastern, now),
+        Ok(parse_by_reference("09:11:12", &Eastern))
+    );
+    assert_eq!(
+        parse_timestamp_tz_at(&owned, Local, now),
+        Ok(parse_by_reference(&owned, &Local))
+    );
+}
+
+/// Test splitting a timestamp into its tokens.
+#[test]
+fn time_tokenize() {
+    use Token::*;
+
+    assert_eq!(
+        tokenize("2018-08-20 + 1h2m"),
+        Ok(vec![
+            DatePart(0..10),
+            Whitespace(10..11),
+            Sign(11..12),
+            Whitespace(12..13),
+            Number(13..14),
+            Unit(14..15),
+            Number(15..16),
+            Unit(16..17),
+        ])
+    );
+    assert_eq!(
+        tokenize("Mon 2018-08-20 09:11:12.5 UTC -1.5 hours"),
+        Ok(vec![
+            Keyword(0..3),
+            Whitespace(3..4),
+            DatePart(4..14),
+            Whitespace(14..15),
+            DatePart(15..25),
+            Whitespace(25..26),
+            Keyword(26..29),
+            Whitespace(29..30),
+            Sign(30..31),
+            Number(31..34),
+            Whitespace(34..35),
+            Unit(35..40),
+        ])
+    );
+    assert_eq!(tokenize("today"), Ok(vec![Keyword(0..5)]));
+    assert_eq!(tokenize("-infinity"), Ok(vec![Keyword(0..9)]));
+    assert_eq!(
+        tokenize("@1529578800"),
+        Ok(vec![Keyword(0..1), Number(1..11)])
+    );
+    assert_eq!(
+        tokenize("in 2h"),
+        Ok(vec![
+            Keyword(0..2),
+            Whitespace(2..3),
+            Number(3..4),
+            Unit(4..5)
+        ])
+    );
+    assert_eq!(
+        tokenize("3s ago"),
+        Ok(vec![
+            Number(0..1),
+            Unit(1..2),
+            Whitespace(2..3),
+            Keyword(3..6)
+        ])
+    );
+    assert_eq!(
+        tokenize("2h from now"),
+        Ok(vec![
+            Number(0..1),
+            Unit(1..2),
+            Whitespace(2..3),
+            Keyword(3..7),
+            Whitespace(7..8),
+            Keyword(8..11),
+        ])
+    );
+
+    // the tokens cover the timestamp, with each token's span indexing into it
+    let ts = "yesterday + 1µs - 2 weeks";
+    let tokens = tokenize(ts).unwrap();
+    assert_eq!(tokens[0].span().start, 0);
+    assert_eq!(tokens.last().unwrap().span().end, ts.len());
+    assert!(tokens
+        .windows(2)
+        .all(|w| w[0].span().end == w[1].span().start));
+    assert_eq!(&ts[tokens[5].span()], "µs");
+
+    // a timestamp which fails to parse has no tokens
+    assert!(matches!(tokenize(""), Err(Error::Format(_))));
+    assert!(matches!(tokenize("+5 bad"), Err(Error::TimeUnit { .. })));
+}
+
+/// Test parsing with `str::parse`.
+#[test]
+fn time_from_str() {
+    assert_eq!(
+        "epoch +1529578800s".parse::<SystemdTimestamp<Utc>>(),
+        Ok(SystemdTimestamp(LocalDateTime::Single(
+            Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap()
+        )))
+    );
+    assert_eq!(
+        "@1529578800s"
+            .parse::<SystemdTimestamp<Local>>()
+            .map(|ts| LocalDateTime::from(ts).single()),
+        Ok(Some(
+            Utc.timestamp_opt(1_529_578_800, 0)
+                .unwrap()
+                .with_timezone(&Local)
+        ))
+    );
+    assert!(matches!(
+        "+5".parse::<SystemdTimestamp<Utc>>(),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test converting into a `UtcTimestamp` with `TryFrom`.
+#[test]
+fn time_try_from() {
+    let expected = Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap();
+    assert_eq!(
+        UtcTimestamp::try_from("epoch +1529578800s"),
+        Ok(UtcTimestamp(expected))
+    );
+    assert_eq!(
+        UtcTimestamp::try_from(String::from("2018-06-21 11:00:00")),
+        Ok(UtcTimestamp(expected))
+    );
+
+    // the newtype dereferences to the `DateTime<Utc>` it holds
+    let timestamp = UtcTimestamp::try_from("@1529578800").unwrap();
+    assert_eq!(*timestamp, expected);
+    assert_eq!(timestamp.timestamp(), 1_529_578_800);
+    assert_eq!(DateTime::<Utc>::from(timestamp), expected);
+
+    assert!(matches!(
+        UtcTimestamp::try_from("+5"),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(matches!(
+        UtcTimestamp::try_from(String::from("5 ago")),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
 /// Test extracting a time from a strftime formatted timestamp.
 #[test]
 fn time_strftime() {
@@ -73,15 +364,15 @@ fn time_strftime() {
 
     assert_eq!(
         parse_timestamp_tz_aux("2018-08-09 07:06:05.123", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::microseconds(123)
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(123)
     );
     assert_eq!(
         parse_timestamp_tz_aux("18-08-09 07:06:05.1", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::microseconds(1)
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(100)
     );
     assert_eq!(
         parse_timestamp_tz_aux("10:11:12.1234", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::microseconds(1234)
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::microseconds(123_400)
     );
 
     assert_eq!(
@@ -119,523 +410,3621 @@ fn time_strftime() {
 
     assert_eq!(
         parse_timestamp_tz_aux("2018-08-09 07:06:05.123", Local),
-        Local.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::microseconds(123)
+        Local.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(123)
     );
     assert_eq!(
         parse_timestamp_tz_aux("18-08-09 07:06:05.1", Local),
-        Local.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::microseconds(1)
+        Local.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(100)
     );
     assert_eq!(
         parse_timestamp_tz_aux("10:11:12.1234", Local),
-        today_time(&Local, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::microseconds(1234)
+        today_time(&Local, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::microseconds(123_400)
     );
 }
 
-/// Test applying an offset to time keywords.
+/// Test extracting a date from a compact `%Y%m%d` timestamp.
 #[test]
-fn offset_word() {
-    let today = parse_timestamp_tz_aux("today", Utc);
-    assert_eq!(
-        parse_timestamp_tz_aux("today +1s", Utc),
-        today + Duration::seconds(1)
-    );
+fn time_compact_date() {
     assert_eq!(
-        parse_timestamp_tz_aux("today +1s2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        parse_timestamp_tz_aux("20180820", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today -1s", Utc),
-        today - Duration::seconds(1)
+        parse_timestamp_tz_aux("20180820 +1h", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 20, 1, 0, 0).unwrap()
     );
+
+    // eight digits which are not a date, and other lengths of digits, are rejected
+    assert!(parse_timestamp_tz("20181320", Utc).is_err());
+    assert!(parse_timestamp_tz("20180231", Utc).is_err());
+    assert!(parse_timestamp_tz("2018082", Utc).is_err());
+    assert!(parse_timestamp_tz("201808201", Utc).is_err());
     assert_eq!(
-        parse_timestamp_tz_aux("today -1s2m", Utc),
-        today - Duration::seconds(1) - Duration::minutes(2)
+        parse_timestamp_tz_aux("@20180820", Utc),
+        Utc.timestamp_opt(20_180_820, 0).unwrap()
     );
+}
 
-    let tomorrow = parse_timestamp_tz_aux("tomorrow", Utc);
+/// Test parsing a timestamp into a single instant in UTC.
+#[test]
+fn timestamp_to_utc() {
     assert_eq!(
-        parse_timestamp_tz_aux("tomorrow +1s", Utc),
-        tomorrow + Duration::seconds(1)
+        parse_to_utc("2018-08-20 09:11:12 + 1h", Utc).unwrap(),
+        Utc.with_ymd_and_hms(2018, 8, 20, 10, 11, 12).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("tomorrow +1s2m", Utc),
-        tomorrow + Duration::seconds(1) + Duration::minutes(2)
+        parse_to_utc("2018-08-20 09:11:12", Eastern).unwrap(),
+        Utc.with_ymd_and_hms(2018, 8, 20, 13, 11, 12).unwrap()
     );
+
+    // US/Eastern falls back from 02:00 EDT to 01:00 EST on 2018-11-04, resolving to the earlier EDT
     assert_eq!(
-        parse_timestamp_tz_aux("tomorrow -1s", Utc),
-        tomorrow - Duration::seconds(1)
+        parse_to_utc("2018-11-04 01:30", Eastern).unwrap(),
+        Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap()
     );
+
+    // an ambiguous time in the local timezone, if any, resolves to its earliest
+    let ts = "2018-11-04 01:30";
     assert_eq!(
-        parse_timestamp_tz_aux("tomorrow -1s2m", Utc),
-        tomorrow - Duration::seconds(1) - Duration::minutes(2)
+        parse_to_utc(ts, Local).unwrap(),
+        parse_timestamp_tz(ts, Local).unwrap().earliest()
     );
 
-    let yesterday = parse_timestamp_tz_aux("yesterday", Utc);
+    assert!(matches!(
+        parse_to_utc("2018-08-20 + 1x", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test parsing a timestamp into the duration from now until it.
+#[test]
+fn time_until() {
     assert_eq!(
-        parse_timestamp_tz_aux("yesterday +1s", Utc),
-        yesterday + Duration::seconds(1)
+        parse_time_until_tz("now + 2h", Utc).unwrap(),
+        Duration::hours(2)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("yesterday +1s2m", Utc),
-        yesterday + Duration::seconds(1) + Duration::minutes(2)
+        parse_time_until_tz("now + 2h", Eastern).unwrap(),
+        Duration::hours(2)
     );
+    assert_eq!(parse_time_until_tz("now", Local).unwrap(), Duration::zero());
     assert_eq!(
-        parse_timestamp_tz_aux("yesterday -1s", Utc),
-        yesterday - Duration::seconds(1)
+        parse_time_until_tz("1h 30m ago", Utc).unwrap(),
+        Duration::minutes(-90)
+    );
+
+    // US/Eastern falls back from 02:00 EDT to 01:00 EST on 2018-11-04, resolving to the earlier EDT
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let before = Utc::now();
+    let until = parse_time_until_tz("2018-11-04 01:30", Eastern).unwrap();
+    let after = Utc::now();
+    assert!(edt - after <= until && until <= edt - before);
+
+    let reject = Parser::new().resolve_ambiguous(AmbiguousStrategy::Reject);
+    assert!(matches!(
+        reject.parse_until_tz("2018-11-04 01:30", Eastern),
+        Err(Error::Ambiguous(_))
+    ));
+    assert!(matches!(
+        parse_time_until_tz("now + 2x", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test classifying a timestamp as absolute or relative to now.
+#[test]
+fn timestamp_meta() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new();
+    let resolution = |ts| parser.parse_meta_tz_at(ts, Utc, now).unwrap().1;
+
+    for ts in [
+        "2018-08-20",
+        "2018-08-20 09:11:12 + 1h",
+        "2018-08-20 09:11:12.5 - 1d",
+        "Mon 2018-08-20",
+        "epoch",
+        "epoch + 1h",
+        "@1529578800",
+        "infinity",
+        "-infinity",
+    ] {
+        assert_eq!(resolution(ts), Resolution::Absolute, "{ts}");
+    }
+    for ts in [
+        "now",
+        "today",
+        "yesterday",
+        "tomorrow",
+        "midnight",
+        "noon",
+        "today + 1h",
+        "09:11:12",
+        "Thu 09:11",
+        "+1h",
+        "-1h",
+        "1h ago",
+        "2h left",
+        "in 2h",
+        "2h from now",
+    ] {
+        assert_eq!(resolution(ts), Resolution::RelativeToNow, "{ts}");
+    }
+
+    // the time is that resolved against now
+    assert_eq!(
+        parser.parse_meta_tz_at("today + 1h", Utc, now),
+        Ok((
+            LocalDateTime::Single(Utc.with_ymd_and_hms(2018, 6, 21, 1, 0, 0).unwrap()),
+            Resolution::RelativeToNow
+        ))
     );
+    assert!(matches!(
+        parser.parse_meta_tz_at("bad", Utc, now),
+        Err(Error::Format(_))
+    ));
+
+    let (time, resolution) = parse_timestamp_tz_meta("@1529578800", Utc).unwrap();
+    assert_eq!(time.single(), Utc.timestamp_opt(1_529_578_800, 0).single());
+    assert_eq!(resolution, Resolution::Absolute);
+}
+
+/// Test parsing a list of timestamps.
+#[test]
+fn timestamp_list() {
+    let today = today_time(&Utc, None);
+    let times = parse_list_tz("today, today + 1h, epoch", Utc).unwrap();
     assert_eq!(
-        parse_timestamp_tz_aux("yesterday -1s2m", Utc),
-        yesterday - Duration::seconds(1) - Duration::minutes(2)
+        times
+            .into_iter()
+            .map(LocalDateTime::unwrap_single)
+            .collect::<Vec<_>>(),
+        [
+            today,
+            today + Duration::hours(1),
+            Utc.timestamp_opt(0, 0).unwrap()
+        ]
     );
 
-    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    // a comma of fractional seconds does not separate timestamps, and every timestamp shares the same now
+    let times = parse_list_tz("2018-08-09 07:06:05,5,  now", Utc).unwrap();
+    assert_eq!(times.len(), 2);
     assert_eq!(
-        parse_timestamp_tz_aux("epoch +1s", Utc),
-        epoch + Duration::seconds(1)
+        times[0].unwrap_single(),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(500)
     );
+    assert!(parse_list_tz("now, now, now", Utc)
+        .unwrap()
+        .windows(2)
+        .all(|pair| pair[0] == pair[1]));
+
+    // the first invalid timestamp is reported along with its index
+    let err = parse_list_tz("today, tomorrow + 5x, bad", Utc).unwrap_err();
+    let Error::List { index, source } = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(*index, 1);
+    assert!(matches!(**source, Error::TimeUnit { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("invalid timestamp at index 1 of the list: "));
+    assert!(std::error::Error::source(&err).is_some());
+
+    for (list, expected) in [("", 0), ("now, ", 1), ("now,, now", 0), ("now, today,", 1)] {
+        assert!(
+            matches!(parse_list_tz(list, Utc), Err(Error::List { index, .. }) if index == expected),
+            "{list}"
+        );
+    }
+}
+
+/// Test parsing a range of two timestamps.
+#[test]
+fn timestamp_range() {
+    let today = today_time(&Utc, None);
+    let single = |(start, end): (LocalDateTime<Utc>, LocalDateTime<Utc>)| {
+        (start.single().unwrap(), end.single().unwrap())
+    };
+
     assert_eq!(
-        parse_timestamp_tz_aux("epoch +1s2m", Utc),
-        epoch + Duration::seconds(1) + Duration::minutes(2)
+        single(parse_range_tz("yesterday .. today", Utc).unwrap()),
+        (today - Duration::days(1), today)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("epoch -1s", Utc),
-        epoch - Duration::seconds(1)
+        single(parse_range_tz("2018-08-09 .. 2018-08-10 + 1h", Utc).unwrap()),
+        (
+            Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2018, 8, 10, 1, 0, 0).unwrap()
+        )
     );
     assert_eq!(
-        parse_timestamp_tz_aux("epoch -1s2m", Utc),
-        epoch - Duration::seconds(1) - Duration::minutes(2)
+        single(parse_range_tz("2018-08-09 07:06:05.5  ..  2018-08-09 07:06:05.75", Utc).unwrap()),
+        (
+            Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(500),
+            Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::milliseconds(750)
+        )
     );
 
-    let now = parse_timestamp_tz_aux("now", Utc);
-    assert!(parse_timestamp_tz_aux("now +1s", Utc) >= now + Duration::seconds(1));
-    assert!(
-        parse_timestamp_tz_aux("now +1s2m", Utc)
-            >= now + Duration::seconds(1) + Duration::minutes(2)
-    );
-    assert!(parse_timestamp_tz_aux("now -1s", Utc) >= now - Duration::seconds(1));
-    assert!(
-        parse_timestamp_tz_aux("now -1s2m", Utc)
-            >= now - Duration::seconds(1) - Duration::minutes(2)
-    );
+    // both ends share the same now, so an empty range is possible
+    let (start, end) = single(parse_range_tz("now .. now", Utc).unwrap());
+    assert_eq!(start, end);
+
+    for ts in [
+        "today .. yesterday",
+        "2018-08-09..2018-08-10",
+        "2018-08-09",
+        "today .. ",
+        "today .. tomorrow .. tomorrow",
+    ] {
+        assert!(
+            matches!(parse_range_tz(ts, Utc), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+    assert!(matches!(
+        parse_range_tz("today .. today + 1x", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
 }
 
-/// Test applying an offset to strftime formatted timestamps.
+/// Test parsing only the time of a timestamp.
 #[test]
-fn offset_strftime() {
+fn time_only() {
+    let parse = |ts| parse_time_tz(ts, Utc).unwrap().single().unwrap();
+
+    let now = Utc::now();
+    assert!(parse("now") >= now);
+    assert_eq!(parse("epoch"), Utc.timestamp_opt(0, 0).unwrap());
+    let today = today_time(&Utc, None);
+    assert_eq!(parse("today"), today);
+    assert_eq!(parse("tomorrow"), today + Duration::days(1));
+    assert_eq!(parse("yesterday"), today - Duration::days(1));
+    assert_eq!(parse("noon"), today + Duration::hours(12));
+    assert_eq!(parse("today"), parse_timestamp_tz_aux("today", Utc));
+
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06:05 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::seconds(1)
+        parse("2018-08-09 07:06:05"),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06:05 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse("18-08-09 07:06"),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06:05 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() - Duration::seconds(1)
+        parse("2018-08-09"),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06:05 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse("10:11:12.1234"),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::microseconds(123_400)
+    );
+    assert_eq!(
+        parse_time_tz("2018-08-09 07:06:05", Local).unwrap(),
+        parse_timestamp_tz("2018-08-09 07:06:05", Local).unwrap()
     );
 
+    // offsets are not accepted
+    for ts in ["today + 1h", "+1h", "1h ago", "@1529578800", ""] {
+        assert!(
+            matches!(parse_time_tz(ts, Utc), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+}
+
+/// Test a time given without a date is anchored to a given date.
+#[test]
+fn time_on_date() {
+    let date = NaiveDate::from_ymd_opt(2001, 2, 3).unwrap();
+    let parse = |ts| {
+        parse_time_on_date_tz(ts, date, Utc)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06:05 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::seconds(1)
+        parse("09:11"),
+        Utc.with_ymd_and_hms(2001, 2, 3, 9, 11, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06:05 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse("09:11:12.5"),
+        Utc.with_ymd_and_hms(2001, 2, 3, 9, 11, 12).unwrap() + Duration::milliseconds(500)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06:05 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() - Duration::seconds(1)
+        parse("09:11 + 1h 30m"),
+        Utc.with_ymd_and_hms(2001, 2, 3, 10, 41, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06:05 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse("23:30 + 1h"),
+        Utc.with_ymd_and_hms(2001, 2, 4, 0, 30, 0).unwrap()
     );
-
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() + Duration::seconds(1)
+        parse_time_on_date_tz("09:11", date, Eastern)
+            .unwrap()
+            .single(),
+        Eastern.with_ymd_and_hms(2001, 2, 3, 9, 11, 0).single()
     );
+
+    // a time with its own date, or a keyword, ignores the date
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse("2018-08-20 09:11"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 0).unwrap()
     );
+    assert_eq!(parse("epoch"), Utc.timestamp_opt(0, 0).unwrap());
+    assert_eq!(parse("today"), today_time(&Utc, None));
+    assert!(parse("now") > Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap());
+
+    assert!(matches!(
+        parse_time_on_date_tz("25:00", date, Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test extracting a time suffixed with the timezone it is in.
+#[test]
+fn time_timezone_suffix() {
+    let ndt = NaiveDate::from_ymd_opt(2018, 8, 20)
+        .unwrap()
+        .and_hms_opt(9, 11, 12)
+        .unwrap();
+    let utc = Utc.from_utc_datetime(&ndt);
+    let local = Local.from_local_datetime(&ndt).unwrap();
+
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() - Duration::seconds(1)
+        parse_timestamp_tz_aux("2018-08-20 09:11:12 UTC", Eastern),
+        utc.with_timezone(&Eastern)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 07:06 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse_timestamp_tz_aux("2018-08-20 09:11:12 local", Eastern),
+        local.with_timezone(&Eastern)
     );
-
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() + Duration::seconds(1)
+        parse_timestamp_tz_aux("2018-08-20 09:11:12 UTC + 1h", Eastern),
+        (utc + Duration::hours(1)).with_timezone(&Eastern)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse_timestamp_tz_aux("Mon 2018-08-20 09:11 UTC", Eastern),
+        (utc - Duration::seconds(12)).with_timezone(&Eastern)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() - Duration::seconds(1)
+        parse_timestamp_tz_aux("today UTC", Utc),
+        today_time(&Utc, None)
     );
+
+    // the time is otherwise interpreted in the given timezone
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 07:06 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse_timestamp_tz_aux("2018-08-20 09:11:12", Eastern),
+        Eastern.from_local_datetime(&ndt).unwrap()
     );
+    assert!(parse_timestamp_tz("2018-08-20 09:11:12 EST", Utc).is_err());
+    assert!(parse_timestamp_tz("UTC", Utc).is_err());
+}
 
+/// Test extracting a time prefixed with a weekday.
+#[test]
+fn time_weekday() {
+    let monday = Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap();
+    assert_eq!(parse_timestamp_tz_aux("Mon 2018-08-20", Utc), monday);
+    assert_eq!(parse_timestamp_tz_aux("Monday 18-08-20", Utc), monday);
+    assert_eq!(parse_timestamp_tz_aux("mon 2018-08-20", Utc), monday);
+    assert_eq!(parse_timestamp_tz_aux("MONDAY 2018-08-20", Utc), monday);
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() + Duration::seconds(1)
+        parse_timestamp_tz_aux("Mon  2018-08-20 09:11:12.5", Utc),
+        monday + Duration::hours(9) + Duration::seconds(672) + Duration::milliseconds(500)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
+        parse_timestamp_tz_aux("Mon 2018-08-20 + 1d", Utc),
+        monday + Duration::days(1)
+    );
+
+    let today = today_time(&Utc, None);
+    let weekday = today.format("%a").to_string();
+    assert_eq!(
+        parse_timestamp_tz_aux(&format!("{weekday} today"), Utc),
+        today
+    );
+}
+
+/// Test the fractional seconds component is read positionally.
+#[test]
+fn time_fraction() {
+    let dt = Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.5", Utc),
+        dt + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.000001", Utc),
+        dt + Duration::microseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.050", Utc),
+        dt + Duration::milliseconds(50)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.000000001", Utc),
+        dt + Duration::nanoseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.9999999", Utc),
+        dt + Duration::nanoseconds(999_999_900)
+    );
+
+    // as with chrono's `%.f`, digits beyond nanosecond precision are truncated rather than overflowing
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05.123456789123456789123456789", Utc),
+        dt + Duration::nanoseconds(123_456_789)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("07:06:05.9999999999", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(7, 6, 5)) + Duration::nanoseconds(999_999_999)
+    );
+
+    // as with chrono's `%.f`, a separator must be followed by at least one digit
+    for ts in [
+        "2018-08-09 07:06:05.",
+        "2018-08-09 07:06:05,",
+        "2018-08-09 07:06:05.a",
+        "07:06:05.5x",
+    ] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+
+    // a ',' separates the fractional seconds component as a '.' does
+    for (comma, dot) in [
+        ("2018-08-09 07:06:05,123", "2018-08-09 07:06:05.123"),
+        ("07:06:05,5", "07:06:05.5"),
+        ("2018-08-09 07:06:05,123 +1h", "2018-08-09 07:06:05.123 +1h"),
+    ] {
+        assert_eq!(
+            parse_timestamp_tz_aux(comma, Utc),
+            parse_timestamp_tz_aux(dot, Utc)
+        );
+    }
+    assert!(parse_timestamp_tz("2018-08-09 07:06:05,1.2", Utc).is_err());
+    assert!(parse_timestamp_tz("2018-08-09 07:06:05.1,2", Utc).is_err());
+
+    // whitespace on either side of the separator is rejected rather than misread
+    for ts in [
+        "09:11:12 .5",
+        "09:11:12. 5",
+        "09:11:12 . 5",
+        "2018-08-09 07:06:05 ,5",
+        "2018-08-09 07:06:05 .5 UTC",
+    ] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Number { .. })),
+            "{ts}"
+        );
+    }
+    assert_eq!(
+        parse_timestamp_tz("09:11:12 .5", Utc).unwrap_err().to_string(),
+        "invalid timestamp number: Fractional seconds are separated from the seconds by whitespace \
+         in `09:11:12 .5` (in \"09:11:12 .5\")"
+    );
+
+    // a separator with whitespace which does not follow a time is just an unparseable timestamp
+    for ts in ["now, later", "foo . bar", "09:11 .5"] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+}
+
+/// Test a leap second is parsed as chrono represents it.
+#[test]
+fn time_leap_second() {
+    let leap = NaiveDate::from_ymd_opt(2016, 12, 31)
+        .unwrap()
+        .and_hms_milli_opt(23, 59, 59, 1_000)
+        .unwrap()
+        .and_utc();
+    assert_eq!(parse_timestamp_tz_aux("2016-12-31 23:59:60", Utc), leap);
+    assert_eq!(
+        parse_timestamp_tz_aux("2016-12-31 23:59:60.5", Utc),
+        leap + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2016-12-31 23:59:60 UTC", Eastern),
+        leap.with_timezone(&Eastern)
+    );
+
+    // offsets disregard the leap second
+    assert_eq!(
+        parse_timestamp_tz_aux("2016-12-31 23:59:60 + 1s", Utc),
+        Utc.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2016-12-31 23:59:60 - 1s", Utc),
+        Utc.with_ymd_and_hms(2016, 12, 31, 23, 59, 59).unwrap()
+    );
+
+    assert!(matches!(
+        parse_timestamp_tz("2016-12-31 23:59:61", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test a parser rejecting timestamps relative to now.
+#[test]
+fn timestamp_absolute_only() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().allow_relative(false);
+
+    assert_eq!(
+        parser.parse_tz_at("2018-08-20", Utc, now),
+        Ok(LocalDateTime::Single(
+            Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap()
+        ))
+    );
+    for ts in [
+        "2018-08-20 09:11:12 + 1h",
+        "Mon 2018-08-20",
+        "epoch + 1h",
+        "@1529578800",
+        "infinity",
+    ] {
+        assert!(parser.parse_tz_at(ts, Utc, now).is_ok(), "{ts}");
+    }
+
+    assert_eq!(
+        parser.parse_tz_at("now + 1h", Utc, now),
+        Err(Error::Format(
+            "Timestamp is relative to now, but only absolute timestamps are allowed (in \"now + 1h\")"
+                .to_owned()
+        ))
+    );
+    for ts in [
+        "now",
+        "today",
+        "09:11:12",
+        "Thu 09:11",
+        "+1h",
+        "1h ago",
+        "2h left",
+        "in 2h",
+    ] {
+        assert!(
+            matches!(parser.parse_tz_at(ts, Utc, now), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+
+    // an invalid timestamp reports why it is invalid rather than whether it is relative
+    assert!(matches!(
+        parser.parse_tz_at("now + 1x", Utc, now),
+        Err(Error::TimeUnit { .. })
+    ));
+    // relative timestamps are allowed by default
+    assert!(Parser::new()
+        .allow_relative(true)
+        .parse_tz_at("now + 1h", Utc, now)
+        .is_ok());
+}
+
+/// Test relative times are resolved against a given reference instant.
+#[test]
+fn time_reference() {
+    let now =
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap() + Duration::nanoseconds(203_918_151);
+    let parse = |timestamp| {
+        parse_timestamp_tz_at(timestamp, Utc, now)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+
+    assert_eq!(parse("now"), now);
+    assert_eq!(
+        parse("today"),
+        Utc.with_ymd_and_hms(2018, 6, 21, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("yesterday -2days"),
+        Utc.with_ymd_and_hms(2018, 6, 18, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("tomorrow +1week"),
+        Utc.with_ymd_and_hms(2018, 6, 29, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("09:11:12 -1day"),
+        Utc.with_ymd_and_hms(2018, 6, 20, 9, 11, 12).unwrap()
+    );
+    assert_eq!(
+        parse("now +4h50m"),
+        now + Duration::hours(4) + Duration::minutes(50)
+    );
+    assert_eq!(
+        parse("4h50m left"),
+        now + Duration::hours(4) + Duration::minutes(50)
+    );
+    assert_eq!(
+        parse("+4h50m"),
+        now + Duration::hours(4) + Duration::minutes(50)
+    );
+    assert_eq!(parse("now -3s"), now - Duration::seconds(3));
+    assert_eq!(parse("3s ago"), now - Duration::seconds(3));
+    assert_eq!(parse("-3s"), now - Duration::seconds(3));
+
+    // the reference instant is converted into the given timezone
+    assert_eq!(
+        parse_timestamp_tz_at("now", Local, now)
+            .unwrap()
+            .single()
+            .unwrap(),
+        now.with_timezone(&Local)
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("today", Local, now)
+            .unwrap()
+            .single()
+            .unwrap(),
+        Local
+            .from_local_datetime(
+                &now.with_timezone(&Local)
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            )
+            .unwrap()
+    );
+}
+
+/// Test relative times are resolved against the time read from a clock.
+#[test]
+fn time_clock() {
+    struct MockClock(Cell<DateTime<Utc>>);
+
+    impl Clock for MockClock {
+        fn now(&self) -> DateTime<Utc> {
+            let now = self.0.get();
+            self.0.set(now + Duration::days(1));
+            now
+        }
+    }
+
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let clock = MockClock(Cell::new(now));
+    let parse = |timestamp| {
+        parse_timestamp_tz_with_clock(timestamp, Utc, &clock)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+
+    // the clock is read once per timestamp
+    assert_eq!(parse("now - 1h"), now - Duration::hours(1));
+    assert_eq!(
+        parse("today"),
+        Utc.with_ymd_and_hms(2018, 6, 22, 0, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse("3s ago"),
+        now + Duration::days(2) - Duration::seconds(3)
+    );
+
+    let system = parse_timestamp_tz_with_clock("now", Utc, &SystemClock).unwrap();
+    assert!(system.earliest() >= now);
+}
+
+/// Test every relative component of a timestamp is resolved against the same instant.
+#[test]
+fn time_reference_consistent() {
+    let now =
+        Utc.with_ymd_and_hms(2018, 6, 21, 23, 59, 59).unwrap() + Duration::microseconds(999_999);
+    let today = Utc.with_ymd_and_hms(2018, 6, 21, 0, 0, 0).unwrap();
+    for _ in 0..10_000 {
+        let parse = |timestamp| {
+            parse_timestamp_tz_at(timestamp, Utc, now)
+                .unwrap()
+                .single()
+                .unwrap()
+        };
+        assert_eq!(parse("now"), now);
+        assert_eq!(parse("now + 0s"), now);
+        assert_eq!(parse("now - 0s"), now);
+        assert_eq!(parse("+0s"), now);
+        assert_eq!(parse("0s ago"), now);
+        assert_eq!(parse("today + 0s"), today);
+    }
+}
+
+/// Test the date of a time without one is today in the given timezone, resolved against the given now.
+#[test]
+fn time_today_reference() {
+    // 23:30 UTC is already the following day two hours east of UTC
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 23, 30, 0).unwrap();
+    let east = FixedOffset::east_opt(2 * 3600).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at("09:11", east, now).unwrap().single(),
+        east.with_ymd_and_hms(2018, 6, 22, 9, 11, 0).single()
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("today", east, now).unwrap().single(),
+        east.with_ymd_and_hms(2018, 6, 22, 0, 0, 0).single()
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("09:11", Utc, now).unwrap().single(),
+        Utc.with_ymd_and_hms(2018, 6, 21, 9, 11, 0).single()
+    );
+
+    // 02:30 UTC is still the previous day in US/Eastern
+    let now = Utc.with_ymd_and_hms(2018, 6, 22, 2, 30, 0).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at("yesterday", Eastern, now)
+            .unwrap()
+            .single(),
+        Eastern.with_ymd_and_hms(2018, 6, 20, 0, 0, 0).single()
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("23:59:59", Eastern, now)
+            .unwrap()
+            .single(),
+        Eastern.with_ymd_and_hms(2018, 6, 21, 23, 59, 59).single()
+    );
+
+    // the time is resolved on today's date directly, so only a time within a daylight saving gap is invalid
+    let now = Utc.with_ymd_and_hms(2018, 3, 11, 12, 0, 0).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at("09:11", Eastern, now)
+            .unwrap()
+            .single(),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 9, 11, 0).single()
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("02:30", Eastern, now),
+        Err(Error::Never(Some(naive(2018, 3, 11, 2, 30))))
+    );
+}
+
+/// Test extracting a time from an RFC 3339 timestamp.
+#[test]
+fn time_rfc3339() {
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 7, 11, 12).unwrap();
+    let parse = |timestamp| {
+        parse_timestamp_rfc3339_tz(timestamp, Utc)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+    assert_eq!(parse("2018-08-20T09:11:12+02:00"), dt);
+    assert_eq!(parse("2018-08-20T07:11:12Z"), dt);
+    assert_eq!(parse("2018-08-20T02:11:12-05:00"), dt);
+    assert_eq!(
+        parse("2018-08-20T07:11:12.5Z"),
+        dt + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse("2018-08-20T09:11:12+02:00 + 1h"),
+        dt + Duration::hours(1)
+    );
+    assert_eq!(
+        parse("2018-08-20T02:11:12-05:00 - 1h"),
+        dt - Duration::hours(1)
+    );
+
+    // the instant is converted into the given timezone
+    assert_eq!(
+        parse_timestamp_rfc3339_tz("2018-08-20T09:11:12+02:00", Local)
+            .unwrap()
+            .single()
+            .unwrap(),
+        dt.with_timezone(&Local)
+    );
+
+    // systemd timestamps are still accepted
+    assert_eq!(parse("2018-08-20 07:11:12"), dt);
+    assert_eq!(parse("epoch"), Utc.timestamp_opt(0, 0).unwrap());
+
+    // RFC 3339 timestamps are only accepted when requested
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-20T07:11:12Z", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test applying an offset to time keywords.
+#[test]
+fn offset_word() {
+    let today = parse_timestamp_tz_aux("today", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("noon + 3h", Utc),
+        today + Duration::hours(15)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("midnight - 1h", Utc),
+        today - Duration::hours(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1s", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1s2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today -1s", Utc),
+        today - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today -1s2m", Utc),
+        today - Duration::seconds(1) - Duration::minutes(2)
+    );
+
+    let tomorrow = parse_timestamp_tz_aux("tomorrow", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("tomorrow +1s", Utc),
+        tomorrow + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("tomorrow +1s2m", Utc),
+        tomorrow + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("tomorrow -1s", Utc),
+        tomorrow - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("tomorrow -1s2m", Utc),
+        tomorrow - Duration::seconds(1) - Duration::minutes(2)
+    );
+
+    let yesterday = parse_timestamp_tz_aux("yesterday", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("yesterday +1s", Utc),
+        yesterday + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("yesterday +1s2m", Utc),
+        yesterday + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("yesterday -1s", Utc),
+        yesterday - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("yesterday -1s2m", Utc),
+        yesterday - Duration::seconds(1) - Duration::minutes(2)
+    );
+
+    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("epoch +1s", Utc),
+        epoch + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("epoch +1s2m", Utc),
+        epoch + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("epoch -1s", Utc),
+        epoch - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("epoch -1s2m", Utc),
+        epoch - Duration::seconds(1) - Duration::minutes(2)
+    );
+
+    let now = parse_timestamp_tz_aux("now", Utc);
+    assert!(parse_timestamp_tz_aux("now +1s", Utc) >= now + Duration::seconds(1));
+    assert!(
+        parse_timestamp_tz_aux("now +1s2m", Utc)
+            >= now + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert!(parse_timestamp_tz_aux("now -1s", Utc) >= now - Duration::seconds(1));
+    assert!(
+        parse_timestamp_tz_aux("now -1s2m", Utc)
+            >= now - Duration::seconds(1) - Duration::minutes(2)
+    );
+}
+
+/// Test applying an offset to strftime formatted timestamps.
+#[test]
+fn offset_strftime() {
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
             + Duration::seconds(1)
             + Duration::minutes(2)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() - Duration::seconds(1)
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06:05 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06:05 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06:05 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06:05 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 07:06 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 +1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 +1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 -1s", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("18-08-09 -1s2m", Utc),
+        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11:12 +1s", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11:12 +1s2m", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12))
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11:12 -1s", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11:12 -1s2m", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12))
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11 +1s", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0)) + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11 +1s2m", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0))
+            + Duration::seconds(1)
+            + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11 -1s", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0)) - Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("10:11 -1s2m", Utc),
+        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0))
+            - Duration::seconds(1)
+            - Duration::minutes(2)
+    );
+}
+
+/// Test the various offset time unit keywords.
+#[test]
+fn offset_time_unit() {
+    let today = parse_timestamp_tz_aux("today", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 seconds", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 second", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 sec", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 s", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 minutes", Utc),
+        today + Duration::minutes(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 minute", Utc),
+        today + Duration::minutes(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 min", Utc),
+        today + Duration::minutes(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 months", Utc),
+        today + Duration::nanoseconds(NSEC_PER_MONTH)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 month", Utc),
+        today + Duration::nanoseconds(NSEC_PER_MONTH)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 M", Utc),
+        today + Duration::nanoseconds(NSEC_PER_MONTH)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 msec", Utc),
+        today + Duration::milliseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 ms", Utc),
+        today + Duration::milliseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 m", Utc),
+        today + Duration::minutes(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 hours", Utc),
+        today + Duration::hours(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 hour", Utc),
+        today + Duration::hours(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 hr", Utc),
+        today + Duration::hours(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 h", Utc),
+        today + Duration::hours(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 days", Utc),
+        today + Duration::days(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 day", Utc),
+        today + Duration::days(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 d", Utc),
+        today + Duration::days(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 weeks", Utc),
+        today + Duration::days(7)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 week", Utc),
+        today + Duration::days(7)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 w", Utc),
+        today + Duration::days(7)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 years", Utc),
+        today + Duration::nanoseconds(NSEC_PER_YEAR)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 year", Utc),
+        today + Duration::nanoseconds(NSEC_PER_YEAR)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 y", Utc),
+        today + Duration::nanoseconds(NSEC_PER_YEAR)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 usec", Utc),
+        today + Duration::microseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 us", Utc),
+        today + Duration::microseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 µs", Utc),
+        today + Duration::microseconds(1)
+    );
+    // the micro sign and the greek small letter mu are the same time unit
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1\u{3bc}s", Utc),
+        parse_timestamp_tz_aux("today + 1\u{b5}s", Utc)
+    );
+    assert_eq!(
+        parse_duration("1\u{3bc}s 2\u{b5}s"),
+        Ok(Duration::microseconds(3))
+    );
+}
+
+/// Test applying a chain of positive and negative offsets.
+#[test]
+fn offset_combined() {
+    let dt = parse_timestamp_tz_aux("2018-08-20", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 - 1h + 30m", Utc),
+        dt - Duration::minutes(30)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 + 9h - 30m", Utc),
+        dt + Duration::hours(8) + Duration::minutes(30)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 09:11:12 + 1d - 2h + 3m", Utc),
+        dt + Duration::hours(9 + 24 - 2) + Duration::minutes(14) + Duration::seconds(12)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 -1h -1h", Utc),
+        dt - Duration::hours(2)
+    );
+
+    // single sign offsets are unchanged
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 + 1h 30m", Utc),
+        dt + Duration::minutes(90)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 - 1h 30m", Utc),
+        dt - Duration::minutes(90)
+    );
+
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at("-1h + 30m", Utc, now)
+            .unwrap()
+            .single(),
+        Some(now - Duration::minutes(30))
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("+1h - 30m + 1s", Utc, now)
+            .unwrap()
+            .single(),
+        Some(now + Duration::minutes(30) + Duration::seconds(1))
+    );
+
+    // offsets too large to apply alone may cancel out
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 + 300000y - 299999y", Utc),
+        dt + Duration::seconds(NSEC_PER_YEAR / 1_000_000_000)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux(
+            "2018-08-20 - 9223372036854775807s + 9223372036854775807s 1h",
+            Utc
+        ),
+        dt + Duration::hours(1)
+    );
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-20 + 300000y - 1y", Utc),
+        Err(Error::Overflow(_))
+    ));
+
+    // a sign within an offset applies to just the following time span
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 + 3h-30m", Utc),
+        dt + Duration::hours(2) + Duration::minutes(30)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 - 3h-30m", Utc),
+        dt - Duration::hours(2) - Duration::minutes(30)
+    );
+    // whereas a sign preceded by whitespace starts another offset
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-20 + 1d -2h+3m", Utc),
+        dt + Duration::hours(21) + Duration::minutes(57)
+    );
+    assert_eq!(parse_duration("3h-30m"), Ok(Duration::minutes(150)));
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-20 + 3h-", Utc),
+        Err(Error::Number { .. })
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-20 + 3h--30m", Utc),
+        Err(Error::Number { .. })
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-20 +-3h", Utc),
+        Err(Error::Number { .. })
+    ));
+}
+
+/// Test parsing an offset relative to now into a signed duration.
+#[test]
+fn offset_signed() {
+    assert_eq!(parse_offset_signed("+2h"), Ok(Duration::hours(2)));
+    assert_eq!(parse_offset_signed("-3s"), Ok(Duration::seconds(-3)));
+    assert_eq!(parse_offset_signed("3s ago"), Ok(Duration::seconds(-3)));
+    assert_eq!(parse_offset_signed("2h left"), Ok(Duration::hours(2)));
+    assert_eq!(
+        parse_offset_signed("- 1h 30m + 15m"),
+        Ok(Duration::minutes(-75))
+    );
+
+    // times are not offsets
+    for ts in ["now + 2h", "2018-08-20 - 1h", "now", "2h", "@5s"] {
+        assert!(
+            matches!(parse_offset_signed(ts), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+    assert!(matches!(
+        parse_offset_signed("+2 bad"),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test adding a time span with calendar months to an arbitrary time.
+#[test]
+fn calendar_span() {
+    let dt = Utc.with_ymd_and_hms(2018, 1, 31, 9, 0, 0).unwrap();
+    assert_eq!(
+        add_calendar_span(dt, "1M"),
+        Ok(Utc.with_ymd_and_hms(2018, 2, 28, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+        add_calendar_span(dt, "3 months"),
+        Ok(Utc.with_ymd_and_hms(2018, 4, 30, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+        add_calendar_span(Utc.with_ymd_and_hms(2016, 2, 29, 9, 0, 0).unwrap(), "1y"),
+        Ok(Utc.with_ymd_and_hms(2017, 2, 28, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+        add_calendar_span(dt, "-1M"),
+        Ok(Utc.with_ymd_and_hms(2017, 12, 31, 9, 0, 0).unwrap())
+    );
+    assert_eq!(
+        add_calendar_span(dt, "1M 1d 2h"),
+        Ok(Utc.with_ymd_and_hms(2018, 3, 1, 11, 0, 0).unwrap())
+    );
+    assert_eq!(add_calendar_span(dt, "2 h"), Ok(dt + Duration::hours(2)));
+
+    // days and weeks remain fixed durations across a daylight saving time transition
+    let before = Eastern.with_ymd_and_hms(2018, 3, 10, 12, 0, 0).unwrap();
+    assert_eq!(
+        add_calendar_span(before, "1d"),
+        Ok(before + Duration::days(1))
+    );
+    assert_eq!(
+        add_calendar_span(before, "1M"),
+        Ok(Eastern.with_ymd_and_hms(2018, 4, 10, 12, 0, 0).unwrap())
+    );
+
+    assert!(matches!(add_calendar_span(dt, ""), Err(Error::Format(_))));
+    assert_eq!(
+        add_calendar_span(dt, "-2 bad"),
+        Err(Error::TimeUnit {
+            unit: "bad".to_owned(),
+            at: 3,
+            input: "-2 bad".to_owned()
+        })
+    );
+
+    let max = DateTime::<Utc>::MAX_UTC;
+    assert!(matches!(
+        add_calendar_span(max, "1M"),
+        Err(Error::Overflow(_))
+    ));
+    assert!(matches!(
+        add_calendar_span(max, "1y"),
+        Err(Error::Overflow(_))
+    ));
+    assert!(matches!(
+        add_calendar_span(DateTime::<Utc>::MIN_UTC, "-1M"),
+        Err(Error::Overflow(_))
+    ));
+}
+
+/// Test the special cases of the parsing algorithm.
+#[test]
+fn offset_special_case() {
+    let now = parse_timestamp_tz_aux("now", Utc);
+    assert!(parse_timestamp_tz_aux("+1s", Utc) >= now + Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("1s left", Utc) >= now + Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("-1s", Utc) >= now - Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("1s ago", Utc) >= now - Duration::seconds(1));
+
+    assert!(
+        parse_timestamp_tz_aux("+1s 2m", Utc) >= now + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert!(
+        parse_timestamp_tz_aux("1s 2m left", Utc)
+            >= now + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert!(
+        parse_timestamp_tz_aux("-1s 2m", Utc) >= now - Duration::seconds(1) - Duration::minutes(2)
+    );
+    assert!(
+        parse_timestamp_tz_aux("1s 2m ago", Utc)
+            >= now - Duration::seconds(1) - Duration::minutes(2)
+    );
+
+    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("@1s", Utc),
+        epoch + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@1s 2m", Utc),
+        epoch + Duration::seconds(1) + Duration::minutes(2)
+    );
+}
+
+/// Test offsets relative to now phrased as "in ..." or "... from now".
+#[test]
+fn offset_natural() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parse = |ts| {
+        parse_timestamp_tz_at(ts, Utc, now)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+
+    assert_eq!(parse("in 2h"), parse("now + 2h"));
+    assert_eq!(parse("2h from now"), parse("now + 2h"));
+    assert_eq!(parse("in 1h 30m"), now + Duration::minutes(90));
+    assert_eq!(parse("1 h 30 m from now"), now + Duration::minutes(90));
+    assert_eq!(parse("in  2h"), now + Duration::hours(2));
+    assert_eq!(parse_offset_signed("2h from now"), Ok(Duration::hours(2)));
+
+    for ts in ["in", "in ", "in2h", "2hfrom now", "2h fromnow", " from now"] {
+        assert!(
+            matches!(parse_timestamp_tz_at(ts, Utc, now), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+    assert_eq!(
+        parse_timestamp_tz_at("in 2x", Utc, now),
+        Err(Error::TimeUnit {
+            unit: "x".to_owned(),
+            at: 4,
+            input: "in 2x".to_owned()
+        })
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("2 x from now", Utc, now),
+        Err(Error::TimeUnit {
+            unit: "x".to_owned(),
+            at: 2,
+            input: "2 x from now".to_owned()
+        })
+    );
+}
+
+/// Test whitespace in the timestamp.
+#[test]
+fn timestamp_whitespace() {
+    let today = parse_timestamp_tz_aux("today", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1s", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1s", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1 s", Utc),
+        today + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 s", Utc),
+        today + Duration::seconds(1)
+    );
+
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1s2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1s2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today +1 s2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 s2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 s 2m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 s 2 m", Utc),
+        today + Duration::seconds(1) + Duration::minutes(2)
+    );
+
+    let now = parse_timestamp_tz_aux("now", Utc);
+    assert!(parse_timestamp_tz_aux("+ 1s", Utc) >= now + Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("+ 1 s", Utc) >= now + Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("1 s left", Utc) >= now + Duration::seconds(1));
+    assert!(parse_timestamp_tz_aux("1  s  left", Utc) >= now + Duration::seconds(1));
+
+    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    assert_eq!(
+        parse_timestamp_tz_aux("@ 1 s", Utc),
+        epoch + Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@  1s", Utc),
+        epoch + Duration::seconds(1)
+    );
+}
+
+/// Test splitting a timestamp into its time and the trailing offsets, both of which must fully parse.
+#[test]
+fn timestamp_split() {
+    let dt = Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 5).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 + 1s + 2m", Utc),
+        dt + Duration::seconds(1) + Duration::minutes(2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 +1s +2m -3h", Utc),
+        dt + Duration::seconds(1) + Duration::minutes(2) - Duration::hours(3)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("2018-08-09 07:06:05 + 1 s  +  2 m", Utc),
+        dt + Duration::seconds(1) + Duration::minutes(2)
+    );
+
+    // text between the time and the offsets, or between the offsets, is rejected
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-09 07:06:05 extra +1s", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-09 +1s 07:06:05", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-09 07:06:05 +1s extra +2m", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+
+    // the error of an invalid offset is reported, rather than the time preceding a later valid offset
+    assert_eq!(
+        parse_timestamp_tz("2018-08-09 + 1x + 2m", Utc),
+        Err(Error::TimeUnit {
+            unit: "x".to_owned(),
+            at: 14,
+            input: "2018-08-09 + 1x + 2m".to_owned()
+        })
+    );
+}
+
+/// Test a timestamp may contain both a `" +"` and a `" -"`, where only a sign beginning a time span is an offset.
+#[test]
+fn timestamp_dual_signs() {
+    let dt = Utc.with_ymd_and_hms(2018, 8, 9, 7, 6, 0).unwrap();
+    let parser = Parser::new().accept_numeric_zone(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+
+    // genuine offsets of either sign are chained
+    assert_eq!(
+        parse("2018-08-09 07:06 + 1h - 30m"),
+        dt + Duration::minutes(30)
+    );
+    assert_eq!(
+        parse("2018-08-09 07:06 - 1h + 30m"),
+        dt - Duration::minutes(30)
+    );
+
+    // a sign which is part of the time, rather than beginning a time span, is not an offset
+    assert_eq!(
+        parse("2018-08-09 07:06 -0500 + 1h"),
+        dt + Duration::hours(6)
+    );
+    assert_eq!(
+        parse("2018-08-09 07:06 -0500 - 1h + 30m"),
+        dt + Duration::hours(4) + Duration::minutes(30)
+    );
+    assert_eq!(
+        parse("2018-08-09 07:06 +0200 - 1h"),
+        dt - Duration::hours(3)
+    );
+    assert_eq!(parse("-infinity + 1h"), DateTime::<Utc>::MIN_UTC);
+
+    // a sign which begins neither a time span nor part of the time is rejected
+    assert!(matches!(
+        parser.parse_tz("2018-08-09 07:06 -x + 1h", Utc),
+        Err(Error::Number { .. })
+    ));
+    assert!(matches!(
+        parser.parse_tz("2018-08-09 07:06 - 1h +", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test parsing standalone time spans.
+#[test]
+fn duration() {
+    assert_eq!(parse_duration("3hours").unwrap(), Duration::hours(3));
+    assert_eq!(
+        parse_duration("2d 5h").unwrap(),
+        Duration::days(2) + Duration::hours(5)
+    );
+    assert_eq!(
+        parse_duration("1y 10 months").unwrap(),
+        Duration::nanoseconds(NSEC_PER_YEAR) + Duration::nanoseconds(10 * NSEC_PER_MONTH)
+    );
+    assert_eq!(
+        parse_duration("30m22s").unwrap(),
+        Duration::minutes(30) + Duration::seconds(22)
+    );
+    assert_eq!(
+        parse_duration("10m 2s 5m").unwrap(),
+        Duration::minutes(15) + Duration::seconds(2)
+    );
+    assert_eq!(
+        parse_duration("10d 2 5m").unwrap(),
+        Duration::days(10) + Duration::minutes(25)
+    );
+    assert_eq!(parse_duration(" 1 µs ").unwrap(), Duration::microseconds(1));
+    assert_eq!(parse_duration("1.5h").unwrap(), Duration::minutes(90));
+    assert_eq!(parse_duration("0.5d").unwrap(), Duration::hours(12));
+    assert_eq!(
+        parse_duration("2.25s").unwrap(),
+        Duration::milliseconds(2250)
+    );
+    assert_eq!(
+        parse_duration("1.5h 2.25s").unwrap(),
+        Duration::minutes(90) + Duration::milliseconds(2250)
+    );
+    assert_eq!(
+        parse_duration("1.0000000001s").unwrap(),
+        Duration::seconds(1)
+    );
+    assert_eq!(
+        parse_duration("0.3333333333333333333333s").unwrap(),
+        Duration::nanoseconds(333_333_333)
+    );
+    assert_eq!(
+        parse_duration("0.6666666666666666666666s").unwrap(),
+        Duration::nanoseconds(666_666_667)
+    );
+    assert_eq!(
+        parse_duration("1s 500ns").unwrap(),
+        Duration::seconds(1) + Duration::nanoseconds(500)
+    );
+    assert_eq!(
+        parse_duration("1nanosecond 2 nanoseconds 3nsec").unwrap(),
+        Duration::nanoseconds(6)
+    );
+    assert_eq!(
+        parse_duration("1500ns").unwrap(),
+        Duration::microseconds(1) + Duration::nanoseconds(500)
+    );
+}
+
+/// Test parsing ISO 8601 durations as time spans.
+#[test]
+fn duration_iso8601() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    assert_eq!(parse_duration("PT1H30M"), Ok(Duration::minutes(90)));
+    assert_eq!(
+        parse_duration("P1DT2H"),
+        Ok(Duration::days(1) + Duration::hours(2))
+    );
+    assert_eq!(parse_duration("P2W"), Ok(Duration::weeks(2)));
+    assert_eq!(
+        parse_duration("P1Y2M"),
+        Ok(Duration::nanoseconds(NSEC_PER_YEAR + 2 * NSEC_PER_MONTH))
+    );
+    assert_eq!(parse_duration("PT2M"), Ok(Duration::minutes(2)));
+    assert_eq!(
+        parse_duration("P1M"),
+        Ok(Duration::nanoseconds(NSEC_PER_MONTH))
+    );
+    assert_eq!(parse_duration("PT0.5S"), Ok(Duration::milliseconds(500)));
+    assert_eq!(parse_duration("PT1,5H"), Ok(Duration::minutes(90)));
+    assert_eq!(
+        parse_duration("P1DT1H1M1S"),
+        Ok(Duration::seconds(86_400 + 3_600 + 60 + 1))
+    );
+    assert_eq!(parse_duration("PT36H"), Ok(Duration::hours(36)));
+
+    // an ISO 8601 duration is also accepted as an offset
+    assert_eq!(parse_offset_signed("-PT1H30M"), Ok(Duration::minutes(-90)));
+    assert_eq!(
+        parse_timestamp_tz_at("PT2H ago", Utc, now)
+            .unwrap()
+            .single(),
+        Some(now - Duration::hours(2))
+    );
+    assert_eq!(
+        parse_timestamp_tz_at("2018-08-20 + P1DT2H", Utc, now)
+            .unwrap()
+            .single(),
+        Utc.with_ymd_and_hms(2018, 8, 21, 2, 0, 0).single()
+    );
+    assert_eq!(
+        Parser::new()
+            .months_as_calendar(true)
+            .parse_tz_at("2018-01-31 + P1M", Utc, now)
+            .unwrap()
+            .single(),
+        Utc.with_ymd_and_hms(2018, 2, 28, 0, 0, 0).single()
+    );
+
+    for span in [
+        "P",
+        "PT",
+        "P1",
+        "P1DT",
+        "PT1D",
+        "P1H",
+        "PM",
+        "P1D1Y",
+        "PT1S1M",
+        "P1DT1HT1M",
+        "P1X",
+        "P-1D",
+        "P1D 5m",
+    ] {
+        assert!(
+            matches!(parse_duration(span), Err(Error::Format(_))),
+            "{span}"
+        );
+    }
+    assert!(matches!(
+        parse_duration("PT1.5.5H"),
+        Err(Error::Number { .. })
+    ));
+    assert!(matches!(
+        parse_duration("P99999999999999999999D"),
+        Err(Error::Overflow(_))
+    ));
+}
+
+/// Test whitespace-free timestamps parse without allocating, while those with whitespace allocate a stripped copy.
+#[test]
+fn timestamp_allocations() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    for (ts, expected) in [
+        ("now", 0),
+        ("2018-08-20", 0),
+        ("2018-08-20 09:11:12 +1h2m3s", 0),
+        ("2018-08-20 09:11:12 + 1h 2m 3s", 1),
+        ("+1h2m3s", 0),
+        ("+ 1h 2m 3s", 1),
+        ("1h 2m 3s ago", 2),
+    ] {
+        let (result, allocations) = count_allocations(|| parse_timestamp_tz_at(ts, Utc, now));
+        assert!(result.is_ok(), "{ts}");
+        assert_eq!(allocations, expected, "{ts}");
+    }
+}
+
+/// Test parsing a time span in place without allocating.
+#[test]
+fn duration_in_place() {
+    let allocations = count_allocations(|| parse_duration_in_place("1h 2m 3s"));
+    assert_eq!(allocations, (Ok(Duration::seconds(3723)), 0));
+    let allocations = count_allocations(|| parse_duration_in_place(" 1.5 hours -30m\t2us "));
+    assert_eq!(
+        allocations,
+        (Ok(Duration::hours(1) + Duration::microseconds(2)), 0)
+    );
+    // an invalid time span is parsed once, only allocating the time unit and input of its error
+    let (result, allocations) = count_allocations(|| parse_duration_in_place("5 bad 1h"));
+    assert!(matches!(result, Err(Error::TimeUnit { .. })));
+    assert_eq!(allocations, 2);
+
+    for span in ["1h 2m 3s", "30m22s", "2d 5h", "1.5h", "3h-30m", "1y 2M"] {
+        assert_eq!(
+            parse_duration_in_place(span),
+            parse_duration(span),
+            "{span}"
+        );
+    }
+
+    // whitespace splitting a number or time unit is removed, as it is when not parsing in place
+    for span in ["1 1s", "1 h ours", "1 . 5h", "1h 2"] {
+        assert_eq!(
+            parse_duration_in_place(span),
+            parse_duration(span),
+            "{span}"
+        );
+    }
+    assert_eq!(parse_duration_in_place("1 1s"), Ok(Duration::seconds(11)));
+    assert_eq!(
+        parse_duration_in_place(" 5 bad"),
+        Err(Error::TimeUnit {
+            unit: "bad".to_owned(),
+            at: 3,
+            input: " 5 bad".to_owned()
+        })
+    );
+    for span in ["", "  ", "+1h", "-1h", "PT1H"] {
+        assert!(
+            matches!(parse_duration_in_place(span), Err(Error::Format(_))),
+            "{span:?}"
+        );
+    }
+    assert!(matches!(
+        parse_duration_in_place("1000000000000000000y"),
+        Err(Error::Overflow(_))
+    ));
+}
+
+/// Test parsing standalone time spans into a `std::time::Duration`.
+#[test]
+fn std_duration() {
+    use std::time::Duration;
+
+    assert_eq!(
+        parse_std_duration("1h30m").unwrap(),
+        Duration::from_secs(5400)
+    );
+    assert_eq!(parse_std_duration("0s").unwrap(), Duration::ZERO);
+    assert_eq!(
+        parse_std_duration("1s 5us").unwrap(),
+        Duration::from_micros(1_000_005)
+    );
+    assert_eq!(
+        parse_std_duration("1 year").unwrap(),
+        Duration::from_secs(31_557_600)
+    );
+}
+
+/// Test the listed time units agree with the lookup of their weights.
+#[test]
+fn time_units_lookup() {
+    for &(unit, nsecs) in TIME_UNITS {
+        assert_eq!(unit_to_nsec(unit), Some(nsecs), "{unit}");
+    }
+    assert_eq!(unit_to_nsec("fortnight"), None);
+}
+
+/// Test listing the understood time units and keywords.
+#[test]
+fn time_units_keywords() {
+    let units = time_units().collect::<Vec<_>>();
+    assert!(units.contains(&"µs"));
+    assert!(units.contains(&"μs"));
+    assert!(units.contains(&"weeks"));
+    assert!(units.contains(&"M"));
+    for unit in units {
+        assert!(parse_duration(format!("1{unit}")).is_ok(), "{unit}");
+    }
+
+    let keywords = time_keywords();
+    assert!(keywords.contains(&"now"));
+    assert!(keywords.contains(&"today"));
+    assert!(keywords.contains(&"-infinity"));
+    for keyword in keywords {
+        assert!(parse_timestamp_tz(keyword, Utc).is_ok(), "{keyword}");
+    }
+}
+
+/// Test an exact keyword, which skips searching for offsets, parses as it does with offsets or another case.
+#[test]
+fn time_keyword_fast_path() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().case_insensitive(true);
+    let parse = |ts: &str| parser.parse_tz_at(ts, Utc, now).unwrap();
+    for keyword in time_keywords() {
+        assert_eq!(
+            parse(keyword),
+            parse(&format!("{keyword} + 0s")),
+            "{keyword}"
+        );
+        assert_eq!(parse(keyword), parse(&keyword.to_uppercase()), "{keyword}");
+    }
+
+    // only an exact keyword takes the fast path, so offsets following it are still applied
+    assert_eq!(parse("now +1h").single(), Some(now + Duration::hours(1)));
+    assert!(matches!(
+        parse_timestamp_tz("nowx", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test breaking durations into the number of each time unit.
+#[test]
+fn duration_breakdown() {
+    assert_eq!(
+        duration_components(Duration::seconds(90061)),
+        DurationComponents {
+            days: 1,
+            hours: 1,
+            minutes: 1,
+            seconds: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        duration_components(Duration::zero()),
+        DurationComponents::default()
+    );
+    assert_eq!(
+        duration_components(Duration::days(8) + Duration::nanoseconds(1_500)),
+        DurationComponents {
+            weeks: 1,
+            days: 1,
+            micros: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        duration_components(-Duration::milliseconds(61_500)),
+        DurationComponents {
+            minutes: -1,
+            seconds: -1,
+            micros: -500_000,
+            ..Default::default()
+        }
+    );
+
+    // months and years use the weights of parsing, so the components parse back into the duration
+    let duration = parse_duration("2y 3M 1w 2d 3h 4m 5s 6us").unwrap();
+    assert_eq!(
+        duration_components(duration),
+        DurationComponents {
+            years: 2,
+            months: 3,
+            weeks: 1,
+            days: 2,
+            hours: 3,
+            minutes: 4,
+            seconds: 5,
+            micros: 6,
+        }
+    );
+    // the components of the largest durations do not overflow
+    assert_eq!(duration_components(Duration::MAX).years, 292_271_023);
+    assert_eq!(duration_components(Duration::MIN).years, -292_271_023);
+}
+
+/// Test formatting durations into time spans.
+#[test]
+fn duration_format() {
+    assert_eq!(format_duration(Duration::zero()), "0s");
+    assert_eq!(format_duration(Duration::seconds(3723)), "1h 2m 3s");
+    assert_eq!(format_duration(Duration::hours(48)), "2d");
+    assert_eq!(format_duration(Duration::days(15)), "2w 1d");
+    assert_eq!(
+        format_duration(Duration::nanoseconds(NSEC_PER_YEAR + NSEC_PER_MONTH)),
+        "1y 1M"
+    );
+    assert_eq!(
+        format_duration(Duration::seconds(1) + Duration::microseconds(2_003)),
+        "1s 2ms 3us"
+    );
+    assert_eq!(format_duration(Duration::nanoseconds(1_500)), "1us 500ns");
+    assert_eq!(format_duration(-Duration::minutes(90)), "-1h 30m");
+
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    assert_eq!(
+        parse_timestamp_tz_at(
+            format!("now {}", format_duration(-Duration::minutes(90))),
+            Utc,
+            now
+        )
+        .unwrap()
+        .single(),
+        Some(now - Duration::minutes(90))
+    );
+
+    // formatted durations round-trip through parsing
+    for duration in [
+        Duration::nanoseconds(1),
+        Duration::microseconds(999_999),
+        Duration::seconds(59),
+        Duration::seconds(90_061),
+        Duration::days(6) + Duration::hours(23) + Duration::nanoseconds(7),
+        Duration::days(400) + Duration::milliseconds(123),
+        Duration::nanoseconds(i64::MAX),
+        Duration::microseconds(i64::MAX),
+    ] {
+        assert_eq!(parse_duration(format_duration(duration)).unwrap(), duration);
+    }
+
+    // beyond `MAX_SPAN` a duration is formatted, but its time span overflows when parsed
+    for duration in [MAX_SPAN + Duration::nanoseconds(1), Duration::MAX] {
+        assert!(matches!(
+            parse_duration(format_duration(duration)),
+            Err(Error::Overflow(_))
+        ));
+    }
+}
+
+/// Test infinite times are clamped to the range of local times in a timezone with an offset.
+#[test]
+fn infinity_with_offset() {
+    for tz in [
+        FixedOffset::east_opt(3600).unwrap(),
+        FixedOffset::west_opt(3600).unwrap(),
+    ] {
+        let max = parse_timestamp_tz_aux("infinity", tz);
+        let min = parse_timestamp_tz_aux("-infinity", tz);
+        // only the instant whose local time would fall out of range is moved in
+        match tz.local_minus_utc() > 0 {
+            true => {
+                assert_eq!(max.naive_local(), NaiveDateTime::MAX);
+                assert_eq!(min, DateTime::<Utc>::MIN_UTC);
+            }
+            false => {
+                assert_eq!(max, DateTime::<Utc>::MAX_UTC);
+                assert_eq!(min.naive_local(), NaiveDateTime::MIN);
+            }
+        }
+        assert!(!max.to_string().is_empty() && !min.to_string().is_empty());
+
+        // the clamped times still saturate rather than being moved by offsets
+        assert_eq!(parse_timestamp_tz_aux("infinity - 1h", tz), max);
+        assert_eq!(parse_timestamp_tz_aux("-infinity + 1h", tz), min);
+    }
+}
+
+/// Test edge cases are parsed a certain way.
+#[test]
+fn timestamp_edge_cases() {
+    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    assert_eq!(parse_timestamp_tz_aux("@", Utc), epoch);
+
+    // an empty offset after a sign, or before a suffix, is rejected
+    for ts in [
+        "+",
+        "-",
+        "+ ",
+        "today + ",
+        "today +  ",
+        "today -",
+        "today - \t",
+        "today + 1h -",
+        "today + 1h +  ",
+        "2018-08-20 09:11:12 + ",
+        " ago",
+        "  left",
+    ] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Format(_))),
+            "{ts}"
+        );
+    }
+    assert_eq!(
+        parse_timestamp_tz("today + ", Utc),
+        Err(Error::Format(
+            "Offset after `+` cannot be empty (in \"today + \")".to_owned()
+        ))
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today", Utc),
+        parse_timestamp_tz_aux("today + 0s", Utc)
+    );
+
+    // ensure infinite times saturate rather than overflow
+    assert_eq!(
+        parse_timestamp_tz_aux("infinity", Utc),
+        DateTime::<Utc>::MAX_UTC
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("-infinity", Utc),
+        DateTime::<Utc>::MIN_UTC
+    );
+    assert!(parse_timestamp_tz_aux("infinity", Local) <= DateTime::<Utc>::MAX_UTC);
+    assert_eq!(
+        parse_timestamp_tz_aux("-infinity", Eastern),
+        DateTime::<Utc>::MIN_UTC + Duration::seconds(4 * 3600 + 56 * 60 + 2)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("infinity - 1000y", Utc),
+        DateTime::<Utc>::MAX_UTC
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("infinity + 1000y", Utc),
+        DateTime::<Utc>::MAX_UTC
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("-infinity - 1000y + 1s", Utc),
+        DateTime::<Utc>::MIN_UTC
+    );
+
+    // ensure a bare number following '@' is a count of seconds
+    assert_eq!(parse_timestamp_tz_aux("@0", Utc), epoch);
+    assert_eq!(
+        parse_timestamp_tz_aux("@1529578800", Utc),
+        Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap()
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@1529578800.5", Utc),
+        Utc.with_ymd_and_hms(2018, 6, 21, 11, 0, 0).unwrap() + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@1529578800", Local),
+        parse_timestamp_tz_aux("@1529578800s", Local)
+    );
+
+    // a fractional seconds component is read positionally, and may follow '@' without any whole seconds
+    assert_eq!(
+        parse_timestamp_tz_aux("@0.000001", Utc),
+        epoch + Duration::microseconds(1)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@.5", Utc),
+        epoch + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@1.000000001", Utc),
+        epoch + Duration::nanoseconds(1_000_000_001)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("@1.1234567891", Utc),
+        epoch + Duration::nanoseconds(1_123_456_789)
+    );
+    for ts in ["@.", "@1.", "@1.5.5", "@..5"] {
+        assert!(
+            matches!(parse_timestamp_tz(ts, Utc), Err(Error::Number { .. })),
+            "{ts}"
+        );
+    }
+    assert!(matches!(
+        parse_timestamp_tz("@99999999999999999999.5", Utc),
+        Err(Error::Overflow(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("@999999999999999999.5", Utc),
+        Err(Error::Overflow(_))
+    ));
+
+    let today = parse_timestamp_tz_aux("today", Utc);
+    // ensure like offsets are combined
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1s 2s", Utc),
+        today + Duration::seconds(3)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1s 4m 2s", Utc),
+        today + Duration::seconds(3) + Duration::minutes(4)
+    );
+
+    // ensure a decimal number is not split across components
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1.5h 30m", Utc),
+        today + Duration::hours(2)
+    );
+
+    // ensure whitespace is removed and is right associative
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 1 1s", Utc),
+        today + Duration::seconds(11)
+    );
+    assert_eq!(
+        parse_timestamp_tz_aux("today + 4m 1 1s", Utc),
+        today + Duration::seconds(11) + Duration::minutes(4)
+    );
+}
+
+/// Test mapping the possible times of a `LocalDateTime`.
+#[test]
+fn local_datetime_map() {
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+
+    assert_eq!(
+        ambiguous.map(|dt| dt.with_timezone(&Utc)),
+        LocalDateTime::Ambiguous(edt, est)
+    );
+    assert_eq!(
+        LocalDateTime::Single(edt).map(|dt| dt.with_timezone(&Eastern)),
+        LocalDateTime::Single(edt.with_timezone(&Eastern))
+    );
+    assert_eq!(
+        LocalDateTime::Ambiguous(edt, est).map(|dt| dt + Duration::hours(1)),
+        LocalDateTime::Ambiguous(edt + Duration::hours(1), est + Duration::hours(1))
+    );
+
+    let checked_add =
+        |dt: DateTime<Utc>| dt.checked_add_signed(Duration::hours(1)).ok_or("overflow");
+    assert_eq!(
+        LocalDateTime::Ambiguous(edt, est).try_map(checked_add),
+        Ok(LocalDateTime::Ambiguous(
+            edt + Duration::hours(1),
+            est + Duration::hours(1)
+        ))
+    );
+    assert_eq!(
+        LocalDateTime::Ambiguous(edt, DateTime::<Utc>::MAX_UTC).try_map(checked_add),
+        Err("overflow")
+    );
+}
+
+/// Test adding and subtracting durations to a `LocalDateTime` without overflowing.
+#[test]
+fn local_datetime_checked() {
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+    let hour = Duration::hours(1);
+
+    assert_eq!(
+        LocalDateTime::Ambiguous(edt, est).checked_add(hour),
+        Some(LocalDateTime::Ambiguous(edt + hour, est + hour))
+    );
+    assert_eq!(
+        LocalDateTime::Single(edt).checked_sub(hour),
+        Some(LocalDateTime::Single(edt - hour))
+    );
+    assert_eq!(
+        LocalDateTime::Ambiguous(edt, DateTime::<Utc>::MAX_UTC).checked_add(hour),
+        None
+    );
+    assert_eq!(
+        LocalDateTime::Single(DateTime::<Utc>::MIN_UTC).checked_sub(hour),
+        None
+    );
+
+    // offsets overflowing a time near the maximum are reported rather than panicking
+    let now = DateTime::<Utc>::MAX_UTC - hour;
+    assert!(matches!(
+        parse_timestamp_tz_at("+ 2h", Utc, now),
+        Err(Error::Overflow(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz_at("now + 1M", Utc, now),
+        Err(Error::Overflow(_))
+    ));
+    assert_eq!(
+        parse_timestamp_tz_at("now + 30m", Utc, now).unwrap(),
+        LocalDateTime::Single(now + Duration::minutes(30))
+    );
+}
+
+/// Test iterating the possible times of a `LocalDateTime`.
+#[test]
+fn local_datetime_iter() {
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+
+    let single = LocalDateTime::Single(edt);
+    assert_eq!(single.iter().collect::<Vec<_>>(), [&edt]);
+    assert_eq!(single.into_iter().collect::<Vec<_>>(), [edt]);
+
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    assert_eq!(ambiguous.iter().count(), 2);
+    assert_eq!(
+        ambiguous.into_iter().collect::<Vec<_>>(),
+        [edt.with_timezone(&Eastern), est.with_timezone(&Eastern)]
+    );
+
+    // each candidate may be tested against a constraint
+    let ambiguous = LocalDateTime::Ambiguous(edt, est);
+    let mut after = Vec::new();
+    for dt in &ambiguous {
+        if *dt > edt {
+            after.push(*dt);
+        }
+    }
+    assert_eq!(after, [est]);
+}
+
+/// Test copying and hashing a `LocalDateTime`.
+#[test]
+fn local_datetime_hash() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let single = LocalDateTime::Single(dt);
+    let ambiguous = LocalDateTime::Ambiguous(dt, dt + Duration::hours(1));
+
+    let mut set = HashSet::new();
+    assert!(set.insert(single));
+    assert!(set.insert(ambiguous));
+    assert!(!set.insert(single));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&LocalDateTime::Single(dt)));
+
+    // copyable for any timezone with a copyable offset
+    let local = LocalDateTime::Single(dt.with_timezone(&Local));
+    let copy = local;
+    assert_eq!(local, copy);
+    let eastern = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    assert_eq!(eastern.earliest(), eastern.earliest());
+    assert_ne!(eastern.earliest(), eastern.latest());
+}
+
+/// Test displaying a `LocalDateTime`.
+#[test]
+fn local_datetime_display() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap() + Duration::milliseconds(250);
+    let single = LocalDateTime::Single(dt);
+    assert_eq!(single.to_string(), "2018-11-04T05:30:00.250+00:00");
+    assert_eq!(format!("{:.0}", single), "2018-11-04T05:30:00+00:00");
+    assert_eq!(format!("{:.6}", single), "2018-11-04T05:30:00.250000+00:00");
+
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    assert_eq!(
+        ambiguous.to_string(),
+        "2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00"
+    );
+}
+
+/// Test requiring a single `LocalDateTime`.
+#[test]
+fn local_datetime_into_single() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    assert_eq!(LocalDateTime::Single(dt).into_single(), Ok(dt));
+
+    let err = parse_timestamp_tz("2018-11-04 01:30", Eastern)
+        .and_then(LocalDateTime::into_single)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::Ambiguous("2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00".to_owned())
+    );
+    assert_eq!(
+        err.to_string(),
+        "ambiguous timestamp in the given timezone: 2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00"
+    );
+}
+
+/// Test unwrapping a single `LocalDateTime`.
+#[test]
+fn local_datetime_unwrap_single() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    assert_eq!(LocalDateTime::Single(dt).unwrap_single(), dt);
+}
+
+/// Test unwrapping an ambiguous `LocalDateTime` panics with both candidates.
+#[test]
+#[should_panic(
+    expected = "on an ambiguous time: 2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00"
+)]
+fn local_datetime_unwrap_single_ambiguous() {
+    parse_timestamp_tz("2018-11-04 01:30", Eastern)
+        .unwrap()
+        .unwrap_single();
+}
+
+/// Test ordering `LocalDateTime`s.
+#[test]
+fn local_datetime_ord() {
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+    let between = Utc.with_ymd_and_hms(2018, 11, 4, 6, 0, 0).unwrap();
+
+    assert!(LocalDateTime::Single(edt) < LocalDateTime::Single(est));
+    assert!(LocalDateTime::Single(est) > LocalDateTime::Single(between));
+
+    // ambiguous times are ordered by their earliest time, then by their latest
+    let ambiguous = LocalDateTime::Ambiguous(edt, est);
+    assert!(ambiguous < LocalDateTime::Single(between));
+    assert!(ambiguous > LocalDateTime::Single(edt));
+    assert!(ambiguous < LocalDateTime::Single(est));
+    assert!(ambiguous < LocalDateTime::Ambiguous(between, est));
+
+    // comparisons across timezones use the instant
+    let eastern = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    assert!(eastern < LocalDateTime::Single(between));
+
+    let mut times = vec![
+        LocalDateTime::Single(est),
+        ambiguous,
+        LocalDateTime::Single(edt),
+        LocalDateTime::Single(between),
+    ];
+    times.sort();
+    assert_eq!(
+        times,
+        [
+            LocalDateTime::Single(edt),
+            ambiguous,
+            LocalDateTime::Single(between),
+            LocalDateTime::Single(est),
+        ]
+    );
+}
+
+/// Test converting between a `LocalDateTime` and a `DateTime`.
+#[test]
+fn local_datetime_from() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let single = LocalDateTime::from(dt);
+    assert_eq!(single, LocalDateTime::Single(dt));
+    assert_eq!(DateTime::try_from(single), Ok(dt));
+
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    let result: Result<DateTime<_>, _> = ambiguous.try_into();
+    assert!(matches!(result, Err(Error::Ambiguous(_))));
+}
+
+/// Test converting a `LocalDateTime` into a `LocalResult`.
+#[test]
+fn local_datetime_local_result() {
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    assert_eq!(
+        LocalResult::from(LocalDateTime::Single(dt)),
+        LocalResult::Single(dt)
+    );
+
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern).unwrap();
+    let result: LocalResult<_> = ambiguous.into();
+    assert_eq!(result, Eastern.with_ymd_and_hms(2018, 11, 4, 1, 30, 0));
+    assert!(matches!(result, LocalResult::Ambiguous(..)));
+
+    // the conversion round trips through `TryFrom<LocalResult>`
+    assert_eq!(LocalDateTime::try_from(result), Ok(ambiguous));
+    assert_eq!(
+        LocalDateTime::try_from(Eastern.with_ymd_and_hms(2018, 3, 11, 2, 30, 0)),
+        Err(Error::Never(None))
+    );
+}
+
+/// Test parsing a timestamp from bytes.
+#[test]
+fn timestamp_bytes() {
+    let parse = |bytes| {
+        parse_timestamp_tz_bytes(bytes, Utc)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap();
+
+    assert_eq!(parse(b"2018-08-20 09:11:12"), dt);
+    assert_eq!(
+        parse(b"2018-08-20 09:11:12 + 1h 5us"),
+        dt + Duration::hours(1) + Duration::microseconds(5)
+    );
+    assert_eq!(
+        parse("2018-08-20 09:11:12 + 5µs".as_bytes()),
+        dt + Duration::microseconds(5)
+    );
+    assert_eq!(
+        parse(b"2018-08-20 09:11:12 - 5\xc2\xb5s"),
+        dt - Duration::microseconds(5)
+    );
+    assert_eq!(
+        parse_timestamp_tz_bytes(b"today", Local).unwrap().single(),
+        parse_timestamp_tz("today", Local).unwrap().single()
+    );
+
+    // the bytes must be UTF-8
+    assert!(matches!(
+        parse_timestamp_tz_bytes(b"2018-08-20 09:11:12 + 5\xb5s", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz_bytes(b"\xff", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test parsing a batch of timestamps, collecting the errors.
+#[test]
+fn timestamp_batch() {
+    let items = [
+        "today",
+        "bad",
+        "2018-08-20 + 1h",
+        "",
+        "now",
+        "now + 1h",
+        "1h ago",
+    ];
+    let results = parse_many_tz(items, Utc);
+    assert_eq!(results.len(), items.len());
+
+    let errors = results
+        .iter()
+        .enumerate()
+        .filter(|(_, result)| result.is_err())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    assert_eq!(errors, [1, 3]);
+
+    assert_eq!(
+        results[0],
+        Ok(LocalDateTime::Single(today_time(&Utc, None)))
+    );
+    assert_eq!(
+        results[2],
+        Ok(LocalDateTime::Single(
+            Utc.with_ymd_and_hms(2018, 8, 20, 1, 0, 0).unwrap()
+        ))
+    );
+
+    // the batch agrees on now
+    let Ok(LocalDateTime::Single(now)) = results[4] else {
+        panic!("expected a single time");
+    };
+    assert_eq!(
+        results[5],
+        Ok(LocalDateTime::Single(now + Duration::hours(1)))
+    );
+    assert_eq!(
+        results[6],
+        Ok(LocalDateTime::Single(now - Duration::hours(1)))
+    );
+
+    let lines = vec![
+        "today".to_owned(),
+        "tomorrow".to_owned(),
+        "today + 1x".to_owned(),
+    ];
+    let mut iter = parse_many_iter_tz(&lines, Local);
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_ok());
+    assert!(matches!(iter.next(), Some(Err(Error::TimeUnit { .. }))));
+    assert!(iter.next().is_none());
+}
+
+/// Test extracting a timestamp from the start of a larger input.
+#[test]
+fn timestamp_prefix() {
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap();
+    let parse = |input| {
+        let (time, rest) = parse_timestamp_prefix_tz(input, Utc).unwrap();
+        (time.single().unwrap(), rest)
+    };
+
+    assert_eq!(
+        parse("2018-08-20 09:11:12 some message"),
+        (dt, " some message")
+    );
+    assert_eq!(parse("2018-08-20 09:11:12"), (dt, ""));
+    assert_eq!(
+        parse("2018-08-20 09:11:12.5\tmessage"),
+        (dt + Duration::milliseconds(500), "\tmessage")
+    );
+    assert_eq!(
+        parse("2018-08-20 09:11:12 + 1h 2m  message 1s"),
+        (dt + Duration::minutes(62), "  message 1s")
+    );
+    assert_eq!(
+        parse("2018-08-20 09:11 12 apples"),
+        (dt - Duration::seconds(12), " 12 apples")
+    );
+    assert_eq!(parse("epoch µs"), (Utc.timestamp_opt(0, 0).unwrap(), " µs"));
+
+    let today = today_time(&Utc, None);
+    assert_eq!(
+        parse("today + 1h ago"),
+        (today + Duration::hours(1), " ago")
+    );
+
+    assert!(matches!(
+        parse_timestamp_prefix_tz("some message", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_prefix_tz("", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test deserializing a timestamp from a config field.
+#[cfg(feature = "serde")]
+#[test]
+fn timestamp_deserialize() {
+    #[derive(Debug, serde::Deserialize)]
+    struct Config {
+        #[serde(deserialize_with = "super::deserialize_utc")]
+        deadline: DateTime<Utc>,
+    }
+
+    let config: Config =
+        serde_json::from_str(r#"{ "deadline": "2018-08-20 09:11:12 + 1h" }"#).unwrap();
+    assert_eq!(
+        config.deadline,
+        Utc.with_ymd_and_hms(2018, 8, 20, 10, 11, 12).unwrap()
+    );
+
+    let config: Config = serde_json::from_str(r#"{ "deadline": "tomorrow + 1week" }"#).unwrap();
+    assert_eq!(config.deadline, today_time(&Utc, None) + Duration::days(8));
+
+    let err = serde_json::from_str::<Config>(r#"{ "deadline": "tomorrow + 1x" }"#).unwrap_err();
+    assert!(err.to_string().contains("invalid time unit at byte 12: x"));
+    assert!(serde_json::from_str::<Config>(r#"{ "deadline": 5 }"#).is_err());
+}
+
+/// Test serializing and deserializing a `LocalDateTime`.
+#[cfg(feature = "serde")]
+#[test]
+fn local_datetime_serde() {
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+
+    let single = LocalDateTime::Single(edt);
+    let json = serde_json::to_string(&single).unwrap();
+    assert_eq!(json, r#""2018-11-04T05:30:00Z""#);
+    assert_eq!(
+        serde_json::from_str::<LocalDateTime<Utc>>(&json).unwrap(),
+        single
+    );
+
+    let ambiguous = parse_timestamp_tz("2018-11-04 01:30", Eastern)
+        .unwrap()
+        .map(|dt| dt.fixed_offset());
+    let json = serde_json::to_string(&ambiguous).unwrap();
+    assert_eq!(
+        json,
+        r#"["2018-11-04T01:30:00-04:00","2018-11-04T01:30:00-05:00"]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<LocalDateTime<FixedOffset>>(&json).unwrap(),
+        ambiguous
+    );
+    assert_eq!(
+        serde_json::from_str::<LocalDateTime<Utc>>(&json).unwrap(),
+        LocalDateTime::Ambiguous(edt, est)
+    );
+
+    for json in [
+        r#""2018-11-04 01:30""#,
+        r#"["2018-11-04T05:30:00Z"]"#,
+        r#"["2018-11-04T05:30:00Z","2018-11-04T06:30:00Z","2018-11-04T07:30:00Z"]"#,
+        "5",
+    ] {
+        assert!(
+            serde_json::from_str::<LocalDateTime<Utc>>(json).is_err(),
+            "{json}"
+        );
+    }
+}
+
+/// Test parsing a timestamp from a command line argument.
+#[cfg(feature = "clap")]
+#[test]
+fn timestamp_arg() {
+    use clap::{Arg, Command};
+
+    let command = Command::new("app")
+        .arg(
+            Arg::new("deadline")
+                .long("deadline")
+                .value_parser(super::parse_utc_arg),
+        )
+        .arg(
+            Arg::new("start")
+                .long("start")
+                .value_parser(super::value_parser_utc()),
+        );
+
+    let matches = command
+        .clone()
+        .try_get_matches_from(["app", "--deadline", "2018-08-20 + 2h", "--start", "epoch"])
+        .unwrap();
+    assert_eq!(
+        matches.get_one::<DateTime<Utc>>("deadline"),
+        Some(&Utc.with_ymd_and_hms(2018, 8, 20, 2, 0, 0).unwrap())
     );
     assert_eq!(
-        parse_timestamp_tz_aux("2018-08-09 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        matches.get_one::<DateTime<Utc>>("start"),
+        Some(&Utc.timestamp_opt(0, 0).unwrap())
     );
 
+    let err = command
+        .try_get_matches_from(["app", "--deadline", "now + 2x"])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    let source = std::error::Error::source(&err).and_then(|e| e.downcast_ref::<Error>());
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 +1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() + Duration::seconds(1)
+        source,
+        Some(&Error::TimeUnit {
+            unit: "x".to_owned(),
+            at: 7,
+            input: "now + 2x".to_owned()
+        })
     );
+}
+
+/// Test that generated timestamps parse into their paired `DateTime`.
+#[cfg(feature = "proptest")]
+#[test]
+fn timestamp_strategy_roundtrip() {
+    let now =
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap() + Duration::nanoseconds(203_918_151);
+    proptest::proptest!(|((timestamp, dt) in super::timestamp_strategy(now))| {
+        proptest::prop_assert_eq!(
+            parse_timestamp_tz_at(&timestamp, Utc, now).unwrap().single(),
+            Some(dt),
+            "{}",
+            timestamp
+        );
+    });
+}
+
+/// Test a parser with months and years of a configured length.
+#[test]
+fn parser_month_len() {
+    let parser = Parser::new()
+        .month_len(Duration::days(30))
+        .year_len(Duration::days(360));
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let dt = Utc.with_ymd_and_hms(2018, 1, 31, 0, 0, 0).unwrap();
+
+    assert_eq!(parse("2018-01-31 + 1M"), dt + Duration::days(30));
+    assert_eq!(parse("2018-01-31 + 1.5 months"), dt + Duration::days(45));
+    assert_eq!(parse("2018-01-31 - 1y"), dt - Duration::days(360));
+    assert_eq!(parse("2018-01-31 + 1y 2M 1d"), dt + Duration::days(421));
+
+    // other time units are unaffected
+    assert_eq!(parse("2018-01-31 + 1w"), dt + Duration::weeks(1));
+
+    // calendar months take precedence over their length
+    let calendar = parser.months_as_calendar(true);
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 +1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        calendar.parse_tz("2018-01-31 + 1M", Utc).unwrap().single(),
+        Utc.with_ymd_and_hms(2018, 2, 28, 0, 0, 0).single()
     );
+}
+
+/// Test a parser applying months and years as calendar months.
+#[test]
+fn parser_months_as_calendar() {
+    let parser = Parser::new().months_as_calendar(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let ymd = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+
+    assert_eq!(parse("2018-01-31 + 1M"), ymd(2018, 2, 28));
+    assert_eq!(parse("2018-03-31 - 1month"), ymd(2018, 2, 28));
+    assert_eq!(parse("2018-01-31 + 1y 1M"), ymd(2019, 2, 28));
+    assert_eq!(parse("2016-02-29 + 1year"), ymd(2017, 2, 28));
+    assert_eq!(parse("2018-01-31 + 1M - 2M"), ymd(2017, 12, 31));
+    assert_eq!(parse("2018-01-31 + 1M-2d"), ymd(2018, 2, 26));
+    assert_eq!(parse("2018-03-31 + 1d-1M"), ymd(2018, 3, 1));
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 -1s", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap() - Duration::seconds(1)
+        parse("2018-01-31 + 1M 1d 2h"),
+        ymd(2018, 3, 1) + Duration::hours(2)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("18-08-09 -1s2m", Utc),
-        Utc.with_ymd_and_hms(2018, 8, 9, 0, 0, 0).unwrap()
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse("2018-01-31 + 1M 1d 2h"),
+        parse("2018-01-31 + 1d 1M 2h")
+    );
+    assert_eq!(parse("@1M"), ymd(1970, 2, 1));
+    assert_eq!(
+        parse("2018-01-31 + 1.5d"),
+        ymd(2018, 2, 1) + Duration::hours(12)
     );
 
+    // calendar months cannot be fractional
+    assert!(matches!(
+        parser.parse_tz("2018-01-31 + 1.5M", Utc),
+        Err(Error::Number { .. })
+    ));
+
+    // the default parser applies months as a fixed duration
     assert_eq!(
-        parse_timestamp_tz_aux("10:11:12 +1s", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) + Duration::seconds(1)
+        Parser::new()
+            .parse_tz("2018-01-31 + 1M", Utc)
+            .unwrap()
+            .single(),
+        Some(ymd(2018, 1, 31) + Duration::nanoseconds(NSEC_PER_MONTH))
+    );
+}
+
+/// Test a parser applying whole days and weeks as calendar days across a daylight saving transition.
+#[test]
+fn parser_days_as_calendar() {
+    // US/Eastern springs forward from 02:00 EST to 03:00 EDT on 2018-03-11
+    let now = Utc.with_ymd_and_hms(2018, 3, 9, 20, 0, 0).unwrap();
+    let parse = |parser: &Parser, ts| {
+        parser
+            .parse_tz_at(ts, Eastern, now)
+            .unwrap()
+            .single()
+            .unwrap()
+    };
+    let calendar = Parser::new().months_as_calendar(true);
+    let fixed = Parser::new();
+    let midnight = |d| Eastern.with_ymd_and_hms(2018, 3, d, 0, 0, 0).unwrap();
+
+    // calendar days keep the wall-clock time, while fixed durations are shifted by the lost hour
+    assert_eq!(parse(&calendar, "today + 1w"), midnight(16));
+    assert_eq!(
+        parse(&fixed, "today + 1w"),
+        midnight(16) + Duration::hours(1)
     );
+    assert_eq!(parse(&calendar, "today + 3d"), midnight(12));
     assert_eq!(
-        parse_timestamp_tz_aux("10:11:12 +1s2m", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12))
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse(&fixed, "today + 3d"),
+        midnight(12) + Duration::hours(1)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("10:11:12 -1s", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12)) - Duration::seconds(1)
+        parse(&calendar, "2018-03-10 12:00 + 1d"),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 12, 0, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("10:11:12 -1s2m", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 12))
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse(&calendar, "2018-03-17 12:00 - 1w"),
+        Eastern.with_ymd_and_hms(2018, 3, 10, 12, 0, 0).unwrap()
     );
 
+    // calendar days are applied after calendar months and before the remaining duration
     assert_eq!(
-        parse_timestamp_tz_aux("10:11 +1s", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0)) + Duration::seconds(1)
+        parse(&calendar, "2018-02-10 + 1M 1d 2h"),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 3, 0, 0).unwrap()
     );
+    assert_eq!(parse(&calendar, "today + 1w-2d"), midnight(14));
+
+    // fractional days remain a fixed duration
     assert_eq!(
-        parse_timestamp_tz_aux("10:11 +1s2m", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0))
-            + Duration::seconds(1)
-            + Duration::minutes(2)
+        parse(&calendar, "today + 1.5d"),
+        midnight(10) + Duration::hours(12)
     );
+
+    // a calendar day landing in the skipped hour never occurs, unless gaps are snapped
+    let ts = "2018-03-10 02:30 + 1d";
+    assert!(matches!(
+        calendar.parse_tz_at(ts, New_York, now),
+        Err(Error::Never(_))
+    ));
     assert_eq!(
-        parse_timestamp_tz_aux("10:11 -1s", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0)) - Duration::seconds(1)
+        parse(&calendar.clone().snap_gaps(true), ts),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 3, 0, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("10:11 -1s2m", Utc),
-        today_time(&Utc, NaiveTime::from_hms_opt(10, 11, 0))
-            - Duration::seconds(1)
-            - Duration::minutes(2)
+        parse(&fixed, ts),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 3, 30, 0).unwrap()
     );
 }
 
-/// Test the various offset time unit keywords.
+/// Test a parser accepting a 'T' between the date and time.
 #[test]
-fn offset_time_unit() {
-    let today = parse_timestamp_tz_aux("today", Utc);
+fn parser_accept_iso_t() {
+    let parser = Parser::new().accept_iso_t(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap();
+
+    assert_eq!(parse("2018-08-20T09:11:12"), dt);
+    assert_eq!(parse("18-08-20T09:11:12"), dt);
+    assert_eq!(parse("2018-08-20T09:11"), dt - Duration::seconds(12));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 seconds", Utc),
-        today + Duration::seconds(1)
+        parse("2018-08-20T09:11:12.5"),
+        dt + Duration::milliseconds(500)
     );
+    assert_eq!(parse("2018-08-20T09:11:12 + 1h"), dt + Duration::hours(1));
+    assert_eq!(parse("2018-08-20 09:11:12"), dt);
+
+    // the default parser rejects the separator
+    assert!(matches!(
+        Parser::new().parse_tz("2018-08-20T09:11:12", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test a parser accepting RFC 3339 times.
+#[test]
+fn parser_accept_rfc3339() {
+    let parser = Parser::new().accept_rfc3339(true);
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 7, 11, 12).unwrap();
+
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 second", Utc),
-        today + Duration::seconds(1)
+        parser
+            .parse_tz("2018-08-20T09:11:12+02:00", Utc)
+            .unwrap()
+            .single(),
+        Some(dt)
     );
+    assert!(matches!(
+        Parser::new().parse_tz("2018-08-20T09:11:12+02:00", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test a parser assuming a time of day for dates without one.
+#[test]
+fn parser_default_time() {
+    let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+    let parser = Parser::new().default_time(noon);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let dt = Utc.with_ymd_and_hms(2018, 8, 20, 12, 0, 0).unwrap();
+
+    assert_eq!(parse("2018-08-20"), dt);
+    assert_eq!(parse("18-08-20"), dt);
+    assert_eq!(parse("2018-08-20 - 1h"), dt - Duration::hours(1));
+    assert_eq!(parse("2018-08-20 09:11"), dt - Duration::minutes(169));
+
+    let today = today_time(&Utc, Some(noon));
+    assert_eq!(parse("today"), today);
+    assert_eq!(parse("yesterday + 2h"), today - Duration::hours(22));
+    assert_eq!(parse("tomorrow - 30m"), today + Duration::minutes(1410));
+    // times of day and relative times are unaffected
+    assert_eq!(parse("midnight"), today_time(&Utc, None));
+    assert_eq!(parse("noon + 1h"), today + Duration::hours(1));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 sec", Utc),
-        today + Duration::seconds(1)
+        parse("09:11:12"),
+        today_time(&Utc, NaiveTime::from_hms_opt(9, 11, 12))
     );
+    assert_eq!(parse("epoch"), Utc.timestamp_opt(0, 0).unwrap());
+}
+
+/// Test a parser defaulting to the end of the day for dates without a time.
+#[test]
+fn parser_default_time_end_of_day() {
+    let end_of_day = NaiveTime::from_hms_micro_opt(23, 59, 59, 999_999).unwrap();
+    let parser = Parser::new().default_time(end_of_day);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let next_day = Utc.with_ymd_and_hms(2018, 8, 21, 0, 0, 0).unwrap();
+
+    assert_eq!(parse("2018-08-20"), next_day - Duration::microseconds(1));
+    assert_eq!(parse("2018-08-20 + 1us"), next_day);
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 s", Utc),
-        today + Duration::seconds(1)
+        parse("18-08-20 + 1d - 1h"),
+        next_day + Duration::hours(23) - Duration::microseconds(1)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 minutes", Utc),
-        today + Duration::minutes(1)
+        parse("today + 1us"),
+        today_time(&Utc, None) + Duration::days(1)
     );
+
+    // the default parser keeps midnight
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 minute", Utc),
-        today + Duration::minutes(1)
+        Parser::new()
+            .parse_tz("2018-08-20 + 1us", Utc)
+            .unwrap()
+            .single(),
+        Some(next_day - Duration::days(1) + Duration::microseconds(1))
     );
+}
+
+/// Test a parser matching keywords and time units regardless of case.
+#[test]
+fn parser_case_insensitive() {
+    let parser = Parser::new().case_insensitive(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 min", Utc),
-        today + Duration::minutes(1)
+        parse("TODAY + 1H"),
+        parse_timestamp_tz_aux("today + 1h", Utc)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 months", Utc),
-        today + Duration::microseconds(USEC_PER_MONTH)
+        parse("Yesterday - 2Days"),
+        parse_timestamp_tz_aux("yesterday - 2days", Utc)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 month", Utc),
-        today + Duration::microseconds(USEC_PER_MONTH)
+        parse("EPOCH + 1Min 2SEC"),
+        Utc.timestamp_opt(62, 0).unwrap()
     );
+    assert_eq!(parse("Noon"), parse_timestamp_tz_aux("noon", Utc));
+    assert_eq!(parse("Infinity - 1y"), DateTime::<Utc>::MAX_UTC);
+    assert_eq!(parse("-INFINITY"), DateTime::<Utc>::MIN_UTC);
+    assert!(parse("Now") >= Utc::now() - Duration::seconds(1));
+    assert!(parse("1H LEFT") > Utc::now());
+    assert!(parse("1h Ago") < Utc::now());
+
+    // the month and minute units remain distinct
+    let epoch = Utc.timestamp_opt(0, 0).unwrap();
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 M", Utc),
-        today + Duration::microseconds(USEC_PER_MONTH)
+        parse("epoch + 1M"),
+        epoch + Duration::nanoseconds(NSEC_PER_MONTH)
+    );
+    assert_eq!(parse("epoch + 1m"), epoch + Duration::minutes(1));
+    assert_eq!(parse("epoch + 1MS"), epoch + Duration::milliseconds(1));
+
+    // the default parser is case sensitive
+    assert!(matches!(
+        Parser::new().parse_tz("TODAY", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        Parser::new().parse_tz("today + 1H", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test a parser truncating the current time.
+#[test]
+fn parser_truncate_now() {
+    let parser = Parser::new().truncate_now_to(TimeUnit::Second);
+    let now = parser.parse_tz("now", Utc).unwrap().single().unwrap();
+    assert_eq!(now.timestamp_subsec_nanos(), 0);
+    assert!(now <= Utc::now());
+    assert!(now > Utc::now() - Duration::seconds(2));
+
+    let now =
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap() + Duration::nanoseconds(203_918_151);
+    let parse = |parser: Parser, ts| parser.parse_tz_at(ts, Utc, now).unwrap().single().unwrap();
+    assert_eq!(
+        parse(Parser::new().truncate_now_to(TimeUnit::Second), "now"),
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 msec", Utc),
-        today + Duration::milliseconds(1)
+        parse(Parser::new().truncate_now_to(TimeUnit::Millisecond), "+1s"),
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 4).unwrap() + Duration::milliseconds(203)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 ms", Utc),
-        today + Duration::milliseconds(1)
+        parse(
+            Parser::new().truncate_now_to(TimeUnit::Microsecond),
+            "1s ago"
+        ),
+        Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 2).unwrap() + Duration::microseconds(203_918)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 m", Utc),
-        today + Duration::minutes(1)
+        parse(Parser::new().truncate_now_to(TimeUnit::Hour), "now - 1m"),
+        Utc.with_ymd_and_hms(2018, 6, 21, 0, 59, 0).unwrap()
     );
+    assert_eq!(parse(Parser::new(), "now"), now);
+}
+
+/// Test a parser rejecting numbers split by whitespace.
+#[test]
+fn parser_reject_split_numbers() {
+    let parser = Parser::new().reject_split_numbers(true);
+    let today = today_time(&Utc, None);
+    let parse = |ts| parser.parse_tz(ts, Utc);
+
+    for ts in [
+        "today + 1 1s",
+        "today + 4m 1 1s",
+        "today + 1. 5h",
+        "1 1s ago",
+        "@1 5s",
+        "- 1 1s",
+    ] {
+        assert!(matches!(parse(ts), Err(Error::Number { .. })), "{ts}");
+    }
+
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 hours", Utc),
-        today + Duration::hours(1)
+        parse("today + 11s").unwrap().single(),
+        Some(today + Duration::seconds(11))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 hour", Utc),
-        today + Duration::hours(1)
+        parse("today + 4m 1s 1s").unwrap().single(),
+        Some(today + Duration::minutes(4) + Duration::seconds(2))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 hr", Utc),
-        today + Duration::hours(1)
+        parse("today + 1 s").unwrap().single(),
+        Some(today + Duration::seconds(1))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 h", Utc),
-        today + Duration::hours(1)
+        parse("today + 1.5 h - 5m").unwrap().single(),
+        Some(today + Duration::minutes(85))
     );
+
+    // the default parser joins the digits
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 days", Utc),
-        today + Duration::days(1)
+        Parser::new()
+            .parse_tz("today + 1 1s", Utc)
+            .unwrap()
+            .single(),
+        Some(today + Duration::seconds(11))
     );
+}
+
+/// Test parsing times on a twelve-hour clock.
+#[test]
+fn parser_accept_am_pm() {
+    let parser = Parser::new().accept_am_pm(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let today = today_time(&Utc, None);
+
+    assert_eq!(parse("09:11 PM"), today + Duration::minutes(21 * 60 + 11));
+    assert_eq!(parse("09:11pm"), today + Duration::minutes(21 * 60 + 11));
+    assert_eq!(parse("9:11 am"), today + Duration::minutes(9 * 60 + 11));
+    assert_eq!(parse("12:00 AM"), today);
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 day", Utc),
-        today + Duration::days(1)
+        parse("2018-08-20 09:11:12 PM"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 21, 11, 12).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 d", Utc),
-        today + Duration::days(1)
+        parse("2018-08-20 09:11 PM - 1h"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 20, 11, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 weeks", Utc),
-        today + Duration::days(7)
+        parse("09:11:12"),
+        today + Duration::seconds(9 * 3600 + 11 * 60 + 12)
     );
+
+    assert!(matches!(
+        parser.parse_tz("13:00 PM", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        Parser::new().parse_tz("09:11 PM", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test parsing a time followed by a numeric timezone offset, distinct from a time span offset.
+#[test]
+fn parser_accept_numeric_zone() {
+    let parser = Parser::new().accept_numeric_zone(true);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let today = today_time(&Utc, None);
+
+    assert_eq!(parse("09:00 +0200"), today + Duration::hours(7));
+    assert_eq!(parse("09:00 +02m"), today + Duration::minutes(9 * 60 + 2));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 week", Utc),
-        today + Duration::days(7)
+        parse("2018-08-20 09:00 -0130"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 10, 30, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 w", Utc),
-        today + Duration::days(7)
+        parse("2018-08-20 09:00 +0200 + 1h"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 8, 0, 0).unwrap()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 years", Utc),
-        today + Duration::microseconds(USEC_PER_YEAR)
+        parse("2018-08-20 09:00 +0200 -0100m"),
+        Utc.with_ymd_and_hms(2018, 8, 20, 5, 20, 0).unwrap()
+    );
+
+    // an invalid numeric timezone offset is instead reported as an offset lacking a time unit
+    assert!(matches!(
+        parser.parse_tz("09:00 +0260", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(matches!(
+        parser.parse_tz("09:00 +02000", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(Parser::new().parse_tz("09:00 +0200", Utc).is_err());
+
+    // multi-byte whitespace before the offset separates it as a space does, rather than panicking
+    for ts in ["09:00\u{a0}+0200", "09:00\u{3000}+0200"] {
+        assert_eq!(
+            parser.parse_tz(ts, Utc).map(|t| t.single()),
+            Ok(Some(today + Duration::hours(7))),
+            "{ts}"
+        );
+    }
+}
+
+/// Test a parser parsing a bare number as a count of the given time unit from now.
+#[test]
+fn parser_bare_number_unit() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().bare_number_unit(Some("s"));
+    let parse = |parser: &Parser, ts| parser.parse_tz_at(ts, Utc, now);
+
+    assert_eq!(
+        parse(&parser, "30").unwrap().single(),
+        Some(now + Duration::seconds(30))
     );
+    assert_eq!(parse(&parser, "0").unwrap().single(), Some(now));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 year", Utc),
-        today + Duration::microseconds(USEC_PER_YEAR)
+        parse(&Parser::new().bare_number_unit(Some("min")), "90")
+            .unwrap()
+            .single(),
+        Some(now + Duration::minutes(90))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 y", Utc),
-        today + Duration::microseconds(USEC_PER_YEAR)
+        parse(
+            &Parser::new()
+                .add_unit("sprint", Duration::days(14))
+                .bare_number_unit(Some("sprint")),
+            "2"
+        )
+        .unwrap()
+        .single(),
+        Some(now + Duration::days(28))
     );
+
+    // '@' remains the epoch, and eight digits remain a compact date
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 usec", Utc),
-        today + Duration::microseconds(1)
+        parse(&parser, "@30").unwrap().single(),
+        Utc.timestamp_opt(30, 0).single()
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 us", Utc),
-        today + Duration::microseconds(1)
+        parse(&parser, "20180820").unwrap().single(),
+        Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).single()
+    );
+
+    // only a timestamp made up solely of digits is a bare number
+    assert!(parse(&parser, "30 + 1h").is_err());
+    assert!(parse(&parser, "3.5").is_err());
+
+    // an unknown time unit is reported when parsing
+    assert!(matches!(
+        parse(&Parser::new().bare_number_unit(Some("x")), "30"),
+        Err(Error::TimeUnit { .. })
+    ));
+
+    // the default parser rejects a bare number
+    assert!(parse(&Parser::new(), "30").is_err());
+    assert!(parse(&parser.bare_number_unit(None), "30").is_err());
+}
+
+/// Test a parser accepting an article in place of the number one.
+#[test]
+fn parser_accept_articles() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().accept_articles(true);
+    let parse = |ts| parser.parse_tz_at(ts, Utc, now).unwrap().single().unwrap();
+    let today = Utc.with_ymd_and_hms(2018, 6, 21, 0, 0, 0).unwrap();
+
+    assert_eq!(parse("a day ago"), now - Duration::days(1));
+    assert_eq!(parse("in an hour"), now + Duration::hours(1));
+    assert_eq!(parse("an hour from now"), now + Duration::hours(1));
+    assert_eq!(parse("a week left"), now + Duration::weeks(1));
+    assert_eq!(parse("today + a day"), today + Duration::days(1));
+    assert_eq!(
+        parse("today + a day an hour 30m"),
+        today + Duration::days(1) + Duration::minutes(90)
     );
+    assert_eq!(parse("- a minute"), now - Duration::minutes(1));
+    assert_eq!(parse("in 2 hours"), now + Duration::hours(2));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 µs", Utc),
-        today + Duration::microseconds(1)
+        Parser::new()
+            .accept_articles(true)
+            .case_insensitive(true)
+            .parse_tz_at("An hour ago", Utc, now)
+            .unwrap()
+            .single(),
+        Some(now - Duration::hours(1))
     );
+
+    // an article is a word of its own, preceding a time unit and not following a number
+    for ts in [
+        "ams ago",
+        "in ans",
+        "1 a day ago",
+        "a ago",
+        "in a",
+        "a 1 day ago",
+    ] {
+        assert!(parser.parse_tz_at(ts, Utc, now).is_err(), "{ts}");
+    }
+
+    // the position of an unknown time unit following an article is that in the input
+    assert!(matches!(
+        parser.parse_tz_at("in an  eon", Utc, now),
+        Err(Error::TimeUnit { at: 7, .. })
+    ));
+
+    // the default parser rejects an article as it is not a number
+    assert!(matches!(
+        Parser::new().parse_tz_at("a day ago", Utc, now),
+        Err(Error::Number { .. })
+    ));
 }
 
-/// Test the special cases of the parsing algorithm.
+/// Test a parser accepting a sign directly after a time or time span.
 #[test]
-fn offset_special_case() {
-    let now = parse_timestamp_tz_aux("now", Utc);
-    assert!(parse_timestamp_tz_aux("+1s", Utc) >= now + Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("1s left", Utc) >= now + Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("-1s", Utc) >= now - Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("1s ago", Utc) >= now - Duration::seconds(1));
+fn parser_adjacent_signs() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let today = Utc.with_ymd_and_hms(2018, 6, 21, 0, 0, 0).unwrap();
+    let parse = |parser: &Parser, ts| {
+        parser
+            .parse_tz_at(ts, Utc, now)
+            .map(|t| t.single().unwrap())
+    };
 
-    assert!(
-        parse_timestamp_tz_aux("+1s 2m", Utc) >= now + Duration::seconds(1) + Duration::minutes(2)
+    // a space is required by default
+    let spaced = Parser::new().require_space_before_sign(true);
+    assert!(matches!(parse(&spaced, "today+1h"), Err(Error::Format(_))));
+    assert!(matches!(parse(&spaced, "today-1h"), Err(Error::Format(_))));
+
+    let parser = Parser::new().require_space_before_sign(false);
+    assert_eq!(parse(&parser, "today+1h"), Ok(today + Duration::hours(1)));
+    assert_eq!(parse(&parser, "today-1h"), Ok(today - Duration::hours(1)));
+    assert_eq!(
+        parse(&parser, "today+1h-30m"),
+        Ok(today + Duration::minutes(30))
     );
-    assert!(
-        parse_timestamp_tz_aux("1s 2m left", Utc)
-            >= now + Duration::seconds(1) + Duration::minutes(2)
+    assert_eq!(
+        parse(&parser, "today+1h -30m"),
+        Ok(today + Duration::minutes(30))
     );
-    assert!(
-        parse_timestamp_tz_aux("-1s 2m", Utc) >= now - Duration::seconds(1) - Duration::minutes(2)
+    assert_eq!(parse(&parser, "today + 1h"), Ok(today + Duration::hours(1)));
+
+    // the signs separating the fields of a date or time are not offsets
+    let date = Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap();
+    assert_eq!(parse(&parser, "2018-08-20"), Ok(date));
+    assert_eq!(
+        parse(&parser, "2018-08-20-1d"),
+        Ok(date - Duration::days(1))
     );
-    assert!(
-        parse_timestamp_tz_aux("1s 2m ago", Utc)
-            >= now - Duration::seconds(1) - Duration::minutes(2)
+    assert_eq!(parse(&parser, "18-08-20+2h"), Ok(date + Duration::hours(2)));
+    assert_eq!(
+        parse(&parser, "2018-08-20 09:11:12-1h"),
+        Ok(Utc.with_ymd_and_hms(2018, 8, 20, 8, 11, 12).unwrap())
+    );
+
+    // each part must still parse
+    assert!(matches!(
+        parse(&parser, "today+1x"),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(matches!(parse(&parser, "today+"), Err(Error::Format(_))));
+}
+
+/// Test a parser ignoring a punctuation mark ending a timestamp.
+#[test]
+fn parser_trim_trailing_punct() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().trim_trailing_punct(true);
+    let parse = |ts| parser.parse_tz_at(ts, Utc, now).unwrap().unwrap_single();
+    let tomorrow = Utc.with_ymd_and_hms(2018, 6, 22, 0, 0, 0).unwrap();
+    let dt = Utc.with_ymd_and_hms(2018, 8, 9, 9, 11, 12).unwrap();
+
+    assert_eq!(parse("tomorrow."), tomorrow);
+    assert_eq!(parse("tomorrow,"), tomorrow);
+    assert_eq!(parse("tomorrow + 1h."), tomorrow + Duration::hours(1));
+    assert_eq!(parse("2018-08-09 09:11:12."), dt);
+    assert_eq!(parse("3h ago."), now - Duration::hours(3));
+
+    // a fraction is still parsed, including when followed by a punctuation mark
+    assert_eq!(
+        parse("2018-08-09 09:11:12.5"),
+        dt + Duration::milliseconds(500)
+    );
+    assert_eq!(
+        parse("2018-08-09 09:11:12,5."),
+        dt + Duration::milliseconds(500)
     );
 
-    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    // only a single punctuation mark is ignored
+    for ts in ["tomorrow..", "tomorrow.,", ".", "tomorrow!"] {
+        assert!(parser.parse_tz_at(ts, Utc, now).is_err(), "{ts}");
+    }
+
+    // a timestamp ending a sentence is extracted with its punctuation
+    let (time, rest) = parser.parse_prefix_tz("tomorrow. Then more", Utc).unwrap();
+    assert_eq!(time.unwrap_single().time(), NaiveTime::MIN);
+    assert_eq!(rest, " Then more");
+
+    assert!(Parser::new().parse_tz_at("tomorrow.", Utc, now).is_err());
+}
+
+/// Test a parser clamping a day beyond the end of its month, while rejecting out of range time fields.
+#[test]
+fn parser_lenient_fields() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parser = Parser::new().lenient_fields(true);
+    let parse = |ts| parser.parse_tz_at(ts, Utc, now).unwrap().unwrap_single();
+    let ymd_hms = |y, m, d, h, mi, s| Utc.with_ymd_and_hms(y, m, d, h, mi, s).unwrap();
+
+    // a day beyond the end of its month is clamped
+    assert_eq!(parse("2018-02-30"), ymd_hms(2018, 2, 28, 0, 0, 0));
+    assert_eq!(parse("2016-02-31"), ymd_hms(2016, 2, 29, 0, 0, 0));
+    assert_eq!(parse("18-04-31 09:11"), ymd_hms(2018, 4, 30, 9, 11, 0));
+
+    // a clamped date may be followed by a time, including a leap second
     assert_eq!(
-        parse_timestamp_tz_aux("@1s", Utc),
-        epoch + Duration::seconds(1)
+        parse("2018-02-30 09:11:12"),
+        ymd_hms(2018, 2, 28, 9, 11, 12)
     );
     assert_eq!(
-        parse_timestamp_tz_aux("@1s 2m", Utc),
-        epoch + Duration::seconds(1) + Duration::minutes(2)
+        parse("2018-02-30 23:59:60"),
+        NaiveDate::from_ymd_opt(2018, 2, 28)
+            .unwrap()
+            .and_hms_nano_opt(23, 59, 59, 1_000_000_000)
+            .unwrap()
+            .and_utc()
+    );
+    assert_eq!(parse("2018-02-30 + 1h"), ymd_hms(2018, 2, 28, 1, 0, 0));
+
+    // valid fields are unaffected
+    assert_eq!(
+        parse("2018-08-20 09:11:12"),
+        ymd_hms(2018, 8, 20, 9, 11, 12)
     );
+
+    // months, days beyond 31, out of range time fields and malformed fields are still rejected
+    for ts in [
+        "2018-13-01",
+        "2018-02-32",
+        "2018-02-00",
+        "2018-02-3x",
+        "2018-02-30 25",
+        "2018-02-30 25:00:00:00",
+        "2018-08-20 100:00",
+        "018-08-20 25:00",
+        "2018-08-20 25:00.5",
+        "2018-08-20 25:00",
+        "2018-08-20 24:00:00",
+        "2018-08-20 09:75",
+        "2018-12-31 23:59:61",
+        "2018-02-30 25:00",
+        "2018-02-30 09:60",
+        "99:99:99",
+        "25:00",
+    ] {
+        assert!(parser.parse_tz_at(ts, Utc, now).is_err(), "{ts}");
+    }
+
+    // the default parser rejects out of range fields
+    for ts in ["2018-02-30", "2018-08-20 25:00", "25:00"] {
+        assert!(
+            matches!(
+                Parser::new().parse_tz_at(ts, Utc, now),
+                Err(Error::Format(_))
+            ),
+            "{ts}"
+        );
+    }
 }
 
-/// Test whitespace in the timestamp.
+/// Test defining custom time units.
 #[test]
-fn timestamp_whitespace() {
-    let today = parse_timestamp_tz_aux("today", Utc);
+fn parser_add_unit() {
+    let parser = Parser::new()
+        .add_unit("sprint", Duration::days(14))
+        .add_unit("shift", Duration::hours(8));
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
+    let today = today_time(&Utc, None);
+
+    assert_eq!(parse("today + 1sprint"), today + Duration::days(14));
     assert_eq!(
-        parse_timestamp_tz_aux("today +1s", Utc),
-        today + Duration::seconds(1)
+        parse("today + 2sprint 1.5shift 1h"),
+        today + Duration::days(28) + Duration::hours(13)
     );
+    assert_eq!(parse("today - 1 shift"), today - Duration::hours(8));
+    assert!(matches!(
+        parser.parse_tz("today + 1Sprint", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1s", Utc),
-        today + Duration::seconds(1)
+        parser
+            .clone()
+            .case_insensitive(true)
+            .parse_tz("today + 1SPRINT", Utc)
+            .unwrap()
+            .single(),
+        Some(today + Duration::days(14))
     );
+
+    // built-in time units take precedence
+    let parser = Parser::new().add_unit("d", Duration::hours(1));
     assert_eq!(
-        parse_timestamp_tz_aux("today +1 s", Utc),
-        today + Duration::seconds(1)
+        parser.parse_tz("today + 1d", Utc).unwrap().single(),
+        Some(today + Duration::days(1))
     );
+
+    // a custom time unit weighing the same as a built-in calendar unit remains a fixed duration
+    let date = Utc.with_ymd_and_hms(2018, 1, 31, 0, 0, 0).unwrap();
+    let parser = Parser::new()
+        .months_as_calendar(true)
+        .month_len(Duration::days(30))
+        .add_unit("moon", Duration::seconds(2_629_800));
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single().unwrap();
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 s", Utc),
-        today + Duration::seconds(1)
+        parse("2018-01-31 + 1moon"),
+        date + Duration::seconds(2_629_800)
+    );
+    assert_eq!(
+        parse("2018-01-31 + 1.5moon"),
+        date + Duration::seconds(3_944_700)
+    );
+    assert_eq!(
+        parse("2018-01-31 + 1M"),
+        Utc.with_ymd_and_hms(2018, 2, 28, 0, 0, 0).unwrap()
     );
+    assert_eq!(
+        Parser::new()
+            .months_as_calendar(true)
+            .add_unit("sol", Duration::days(1))
+            .parse_tz("2018-03-10 12:00 + 1sol", Eastern)
+            .unwrap()
+            .single()
+            .unwrap(),
+        Eastern.with_ymd_and_hms(2018, 3, 11, 13, 0, 0).unwrap()
+    );
+
+    // the default parser does not understand custom time units
+    assert!(matches!(
+        parse_timestamp_tz("today + 1sprint", Utc),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
+/// Test defining an invalid custom time unit.
+#[test]
+#[should_panic(expected = "must be positive")]
+fn parser_add_unit_negative() {
+    let _ = Parser::new().add_unit("backwards", Duration::hours(-1));
+}
+
+/// Test parsing dates in day, month, year order.
+#[test]
+fn parser_date_format() {
+    let parser = Parser::new().date_format(DateOrder::DayMonthYear);
+    let parse = |ts| parser.parse_tz(ts, Utc).unwrap().single();
 
     assert_eq!(
-        parse_timestamp_tz_aux("today +1s2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        parse("20.08.2018"),
+        Some(Utc.with_ymd_and_hms(2018, 8, 20, 0, 0, 0).unwrap())
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1s2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        parse("20.08.2018 09:11:12"),
+        Some(Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap())
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today +1 s2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        parse("20.08.2018 09:11 + 1h"),
+        Some(Utc.with_ymd_and_hms(2018, 8, 20, 10, 11, 0).unwrap())
     );
+
+    // other formats, including fractional seconds, are unaffected
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 s2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        parse("2018-08-20 09:11:12.5"),
+        Some(Utc.with_ymd_and_hms(2018, 8, 20, 9, 11, 12).unwrap() + Duration::milliseconds(500))
+    );
+    assert!(parser.parse_tz("31.02.2018", Utc).is_err());
+
+    // the default parser rejects dotted dates
+    assert!(matches!(
+        Parser::new().parse_tz("20.08.2018", Utc),
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test a parser resolving a time repeated by a daylight saving time fall-back.
+#[test]
+fn parser_resolve_ambiguous() {
+    // US/Eastern falls back from 02:00 EDT to 01:00 EST on 2018-11-04
+    let ts = "2018-11-04 01:30";
+    let edt = Utc.with_ymd_and_hms(2018, 11, 4, 5, 30, 0).unwrap();
+    let est = Utc.with_ymd_and_hms(2018, 11, 4, 6, 30, 0).unwrap();
+
+    assert_eq!(
+        Parser::new().parse_tz(ts, Eastern).unwrap(),
+        LocalDateTime::Ambiguous(edt.with_timezone(&Eastern), est.with_timezone(&Eastern))
     );
+
+    let earliest = Parser::new().resolve_ambiguous(AmbiguousStrategy::Earliest);
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 s 2m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        earliest.parse_tz(ts, Eastern).unwrap(),
+        LocalDateTime::Single(edt.with_timezone(&Eastern))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 s 2 m", Utc),
-        today + Duration::seconds(1) + Duration::minutes(2)
+        earliest.parse_tz("2018-11-04 01:30 + 1h", Eastern).unwrap(),
+        LocalDateTime::Single((edt + Duration::hours(1)).with_timezone(&Eastern))
     );
 
-    let now = parse_timestamp_tz_aux("now", Utc);
-    assert!(parse_timestamp_tz_aux("+ 1s", Utc) >= now + Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("+ 1 s", Utc) >= now + Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("1 s left", Utc) >= now + Duration::seconds(1));
-    assert!(parse_timestamp_tz_aux("1  s  left", Utc) >= now + Duration::seconds(1));
+    let latest = Parser::new().resolve_ambiguous(AmbiguousStrategy::Latest);
+    assert_eq!(
+        latest.parse_tz(ts, Eastern).unwrap(),
+        LocalDateTime::Single(est.with_timezone(&Eastern))
+    );
 
-    let epoch = parse_timestamp_tz_aux("epoch", Utc);
+    let reject = Parser::new().resolve_ambiguous(AmbiguousStrategy::Reject);
     assert_eq!(
-        parse_timestamp_tz_aux("@ 1 s", Utc),
-        epoch + Duration::seconds(1)
+        reject.parse_tz(ts, Eastern),
+        Err(Error::Ambiguous(
+            "2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00".to_owned()
+        ))
     );
+
+    // unambiguous times are unaffected by the strategy
+    let dt = Utc.with_ymd_and_hms(2018, 11, 4, 8, 30, 0).unwrap();
     assert_eq!(
-        parse_timestamp_tz_aux("@  1s", Utc),
-        epoch + Duration::seconds(1)
+        reject.parse_tz("2018-11-04 03:30", Eastern).unwrap(),
+        LocalDateTime::Single(dt.with_timezone(&Eastern))
     );
 }
 
-/// Test edge cases are parsed a certain way.
+/// Test a parser snapping a time skipped by the timezone to the end of the gap.
 #[test]
-fn timestamp_edge_cases() {
-    let epoch = parse_timestamp_tz_aux("epoch", Utc);
-    assert_eq!(parse_timestamp_tz_aux("@", Utc), epoch);
+fn parser_snap_gaps() {
+    let parser = Parser::new().snap_gaps(true);
+    let parse = |ts| parser.parse_tz(ts, GapZone).unwrap().single().unwrap();
+    let gap_end = Utc.with_ymd_and_hms(2018, 3, 25, 0, 0, 0).unwrap();
 
-    let today = parse_timestamp_tz_aux("today", Utc);
-    // ensure like offsets are combined
+    // midnight of the gap date is skipped
+    assert_eq!(parse("2018-03-25"), gap_end);
+    assert_eq!(parse("2018-03-25 00:30"), gap_end);
+    assert_eq!(parse("2018-03-25 00:59:59.999"), gap_end);
+    assert_eq!(parse("2018-03-25 + 2h"), gap_end + Duration::hours(2));
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1s 2s", Utc),
-        today + Duration::seconds(3)
+        parser
+            .parse_tz_at("today", GapZone, gap_end + Duration::hours(5))
+            .unwrap()
+            .single()
+            .unwrap(),
+        gap_end
     );
+
+    // times outside the gap are unaffected
+    assert_eq!(parse("2018-03-25 01:00"), gap_end);
+    assert_eq!(parse("2018-03-24 23:00"), gap_end - Duration::hours(1));
+
+    // US/Eastern springs forward from 02:00 EST to 03:00 EDT on 2018-03-11
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1s 4m 2s", Utc),
-        today + Duration::seconds(3) + Duration::minutes(4)
+        parser
+            .parse_tz("2018-03-11 02:30", Eastern)
+            .unwrap()
+            .single(),
+        Some(
+            Utc.with_ymd_and_hms(2018, 3, 11, 7, 0, 0)
+                .unwrap()
+                .with_timezone(&Eastern)
+        )
     );
 
-    // ensure whitespace is removed and is right associative
+    // the default parser rejects a time in the gap
     assert_eq!(
-        parse_timestamp_tz_aux("today + 1 1s", Utc),
-        today + Duration::seconds(11)
+        Parser::new().parse_tz("2018-03-25", GapZone),
+        Err(Error::Never(Some(naive(2018, 3, 25, 0, 0))))
     );
     assert_eq!(
-        parse_timestamp_tz_aux("today + 4m 1 1s", Utc),
-        today + Duration::seconds(11) + Duration::minutes(4)
+        Parser::new().parse_tz("2018-03-25 00:30 + 1h", GapZone),
+        Err(Error::Never(Some(naive(2018, 3, 25, 0, 30))))
     );
 }
 
@@ -645,6 +4034,20 @@ fn timestamp_edge_cases() {
 
 #[test]
 fn invalid_format() {
+    // weekday must match the date
+    assert!(matches!(
+        parse_timestamp_tz("Tue 2018-08-20", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("tuesday 2018-08-20 + 1d", Utc),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        parse_timestamp_tz("Mo 2018-08-20", Utc),
+        Err(Error::Format(_))
+    ));
+
     // space required before modifer
     assert!(matches!(
         parse_timestamp_tz("today+1s", Utc),
@@ -666,14 +4069,19 @@ fn invalid_format() {
         Err(Error::Format(_))
     ));
 
-    // both modifiers
+    // consecutive modifiers
     assert!(matches!(
         parse_timestamp_tz("today + - 1s", Utc),
         Err(Error::Format(_))
     ));
 
     assert!(matches!(
-        parse_timestamp_tz("today - 1s + 5m", Utc),
+        parse_timestamp_tz("today - 1s + - 5m", Utc),
+        Err(Error::Format(_))
+    ));
+
+    assert!(matches!(
+        parse_timestamp_tz("+ - 1s", Utc),
         Err(Error::Format(_))
     ));
 
@@ -689,35 +4097,394 @@ fn invalid_format() {
     ));
 }
 
+#[test]
+fn invalid_duration() {
+    assert!(matches!(parse_duration(""), Err(Error::Format(_))));
+    assert!(matches!(parse_duration("  "), Err(Error::Format(_))));
+
+    // signs are part of a timestamp, not a time span
+    assert!(matches!(parse_duration("+5s"), Err(Error::Format(_))));
+    assert!(matches!(parse_duration("- 5s"), Err(Error::Format(_))));
+
+    assert!(matches!(parse_duration("5"), Err(Error::TimeUnit { .. })));
+    assert!(matches!(
+        parse_duration("5s 6 bad"),
+        Err(Error::TimeUnit { .. })
+    ));
+    assert!(matches!(
+        parse_duration("1000000000d 100s"),
+        Err(Error::Overflow(_))
+    ));
+}
+
+#[test]
+fn invalid_std_duration() {
+    assert!(matches!(parse_std_duration("+5s"), Err(Error::Format(_))));
+    assert!(matches!(
+        parse_std_duration("5"),
+        Err(Error::TimeUnit { .. })
+    ));
+}
+
 #[test]
 fn invalid_number() {
-    // numbers that would overflow fail
+    // malformed decimal numbers
     assert!(matches!(
-        parse_timestamp_tz("2018-08-09 07:06:05.123456789123456789123456789", Utc),
-        Err(Error::Number(_))
+        parse_timestamp_tz("+1.5.5h", Utc),
+        Err(Error::Number { .. })
     ));
 
     assert!(matches!(
-        parse_timestamp_tz("+1000000000d 100s", Utc),
-        Err(Error::Number(_))
+        parse_timestamp_tz("+1.h", Utc),
+        Err(Error::Number { .. })
     ));
 
     assert!(matches!(
-        parse_timestamp_tz("+100s 1000000000d", Utc),
-        Err(Error::Number(_))
+        parse_timestamp_tz("+1h .5m", Utc),
+        Err(Error::Number { .. })
+    ));
+
+    // empty fraction
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-09 07:06:05.", Utc),
+        Err(Error::Format(_))
+    ));
+
+    // signed fraction
+    assert!(matches!(
+        parse_timestamp_tz("2018-08-09 07:06:05.+5", Utc),
+        Err(Error::Format(_))
     ));
 
     // number contains whitespace
     assert!(matches!(
         parse_timestamp_tz("2018-08-09 07:06:05.123 4", Utc),
-        Err(Error::Number(_))
+        Err(Error::Format(_))
     ));
 
     // number contains characters
     assert!(matches!(
         parse_timestamp_tz("2018-08-09 07:06:05.123a4", Utc),
-        Err(Error::Number(_))
+        Err(Error::Format(_))
+    ));
+}
+
+/// Test multi-byte characters and whitespace anywhere in a timestamp are rejected or parsed,
+/// never sliced mid-character.
+#[test]
+fn invalid_multibyte() {
+    let now = Utc.with_ymd_and_hms(2018, 6, 21, 1, 2, 3).unwrap();
+    let parsers = [
+        Parser::new(),
+        Parser::new()
+            .case_insensitive(true)
+            .accept_iso_t(true)
+            .accept_am_pm(true)
+            .accept_numeric_zone(true)
+            .months_as_calendar(true),
+        // every option which slices a timestamp
+        Parser::new()
+            .case_insensitive(true)
+            .accept_iso_t(true)
+            .accept_rfc3339(true)
+            .accept_am_pm(true)
+            .accept_numeric_zone(true)
+            .accept_articles(true)
+            .trim_trailing_punct(true)
+            .lenient_fields(true)
+            .reject_split_numbers(true)
+            .require_space_before_sign(true)
+            .bare_number_unit(Some("s"))
+            .add_unit("fortnight", Duration::weeks(2)),
+    ];
+    let bases = [
+        "2018-08-20 09:11:12.123 +1h2m3s",
+        "today + 1.5h -30m",
+        "@1529578800",
+        "@1s 2m",
+        "in 4h50m",
+        "4h 50m left",
+        "3s ago",
+        "1h from now",
+        "+ 1d -2h+3m",
+        "2018-08-20T09:11:12 UTC",
+        "09:00 +0200",
+        "10:30 pm",
+        "2018-08-20T09:11:12+02:00",
+        "2018-8-2 9:1",
+        "an hour ago",
+        "tomorrow.",
+        "now + 5 - 1 fortnight",
+    ];
+    for base in bases {
+        let positions = base.char_indices().map(|(p, _)| p).chain([base.len()]);
+        for p in positions {
+            for insert in [
+                "µs", "μs", "µ", "ß", "İ", "€", "🕐", "\u{a0}", "\u{2009}", "\u{3000}",
+            ] {
+                let ts = format!("{}{insert}{}", &base[..p], &base[p..]);
+                let _ = is_valid_timestamp(&ts);
+                let _ = parse_time_tz(&ts, Utc);
+                let _ = parse_timestamp_prefix_tz(&ts, Utc);
+                let _ = parse_duration_in_place(&ts);
+                for result in [
+                    parse_duration(&ts).map(drop),
+                    parse_offset_signed(&ts).map(drop),
+                ] {
+                    if let Err(Error::TimeUnit { at, .. }) = result {
+                        assert!(ts.is_char_boundary(at), "{ts:?} at {at}");
+                    }
+                }
+                for parser in &parsers {
+                    if let Err(Error::TimeUnit { at, input, .. }) =
+                        parser.parse_tz_at(&ts, Utc, now)
+                    {
+                        assert!(ts.is_char_boundary(at), "{ts:?} at {at}");
+                        assert_eq!(input, ts);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_overflow() {
+    // numbers that would overflow fail
+    assert!(matches!(
+        parse_timestamp_tz("+1000000000d 100s", Utc),
+        Err(Error::Overflow(_))
+    ));
+
+    assert!(matches!(
+        parse_timestamp_tz("+100s 1000000000d", Utc),
+        Err(Error::Overflow(_))
+    ));
+
+    assert!(matches!(
+        parse_timestamp_tz("today + 100000000000000000000s", Utc),
+        Err(Error::Overflow(_))
+    ));
+
+    assert!(matches!(
+        parse_duration("100000000000000000000ns"),
+        Err(Error::Overflow(_))
+    ));
+
+    assert!(matches!(
+        parse_duration("9223372036854775807ns 9223372036854775807us"),
+        Err(Error::Overflow(_))
+    ));
+}
+
+/// Test the largest time span is accepted, and anything beyond it rejected, in either direction.
+#[test]
+fn invalid_overflow_max_span() {
+    assert_eq!(parse_duration("9223372036854775807us"), Ok(MAX_SPAN));
+    assert_eq!(parse_duration("9223372036854775806us 1000ns"), Ok(MAX_SPAN));
+    assert_eq!(
+        parse_duration("9223372036854775806us 999ns"),
+        Ok(MAX_SPAN - Duration::nanoseconds(1))
+    );
+    assert_eq!(
+        parse_duration("9223372036854775807us 1us -1us"),
+        Ok(MAX_SPAN)
+    );
+    assert_eq!(parse_offset_signed("-9223372036854775807us"), Ok(-MAX_SPAN));
+    assert_eq!(parse_duration(format_duration(MAX_SPAN)), Ok(MAX_SPAN));
+
+    for span in [
+        "9223372036854775807us 1us",
+        "9223372036854775807us 1ns",
+        "9223372036854775807us 0.001us",
+        "292272y",
+    ] {
+        assert!(
+            matches!(parse_duration(span), Err(Error::Overflow(_))),
+            "{span}"
+        );
+    }
+    for offset in ["-9223372036854775807us 1us", "+9223372036854775807us 1us"] {
+        assert!(
+            matches!(parse_offset_signed(offset), Err(Error::Overflow(_))),
+            "{offset}"
+        );
+    }
+    assert!(matches!(
+        parse_timestamp_tz("today + 9223372036854775807us 1us", Utc),
+        Err(Error::Overflow(_))
+    ));
+}
+
+/// Test checking whether timestamps are well formed.
+#[test]
+fn timestamp_valid() {
+    for ts in [
+        "now",
+        "2018-08-20 09:11:12.5 + 1h 30m",
+        "Mon 2018-08-20",
+        "3s ago",
+        "@1529578800",
+        "infinity",
+        // overflow and times which do not exist in a timezone are not checked
+        "today + 300000y",
+    ] {
+        assert!(is_valid_timestamp(ts), "{ts}");
+    }
+
+    // each rejected category
+    for ts in ["", "2018-08-20 09:11:12 extra", "Tue 2018-08-20"] {
+        assert!(!is_valid_timestamp(ts), "{ts}");
+        assert!(matches!(parse_timestamp_tz(ts, Utc), Err(Error::Format(_))));
+    }
+    assert!(!is_valid_timestamp("+1.5.5h"));
+    assert!(matches!(
+        parse_timestamp_tz("+1.5.5h", Utc),
+        Err(Error::Number { .. })
+    ));
+    for ts in ["today + 5x", "5 ago"] {
+        assert!(!is_valid_timestamp(ts), "{ts}");
+        assert!(matches!(
+            parse_timestamp_tz(ts, Utc),
+            Err(Error::TimeUnit { .. })
+        ));
+    }
+}
+
+/// Test the position of an unknown time unit refers to the original timestamp.
+#[test]
+fn error_position() {
+    let position = |ts: &str| match parse_timestamp_tz(ts, Utc) {
+        Err(Error::TimeUnit { unit, at, input }) => {
+            assert_eq!(input, ts);
+            assert_eq!(&ts[at..(at + unit.len())], unit, "{ts}");
+            at
+        }
+        res => panic!("{ts}: {res:?}"),
+    };
+
+    assert_eq!(position("today + 1h 5x 2m"), 12);
+    assert_eq!(position("today + 1h - 2 m 5 bad 3s"), 19);
+    assert_eq!(position("2018-08-20 +  4 hrs"), 16);
+    assert_eq!(position("+1h   2 µsx"), 8);
+    assert_eq!(position("1h 2 x ago"), 5);
+    assert_eq!(position("2 fortnight left"), 2);
+    assert_eq!(position("@1 s 5 q"), 7);
+    assert_eq!(
+        parse_timestamp_tz("today + 1h 5", Utc),
+        Err(Error::TimeUnit {
+            unit: String::new(),
+            at: 12,
+            input: "today + 1h 5".to_owned()
+        })
+    );
+
+    let err = parse_duration("1h 30 mn").unwrap_err();
+    assert_eq!(
+        err,
+        Error::TimeUnit {
+            unit: "mn".to_owned(),
+            at: 6,
+            input: "1h 30 mn".to_owned()
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid time unit at byte 6: mn (in \"1h 30 mn\")"
+    );
+}
+
+/// Test errors describe the full input they were parsed from.
+#[test]
+fn error_input() {
+    let message = |ts: &str| parse_timestamp_tz(ts, Utc).unwrap_err().to_string();
+
+    assert_eq!(
+        message("today +5"),
+        "invalid time unit at byte 8:  (in \"today +5\")"
+    );
+    assert!(
+        message("2018-08-09 07:06:05 + 5s ~3s").ends_with("(in \"2018-08-09 07:06:05 + 5s ~3s\")")
+    );
+    assert!(message("today + 100000000000000000000s")
+        .ends_with("(in \"today + 100000000000000000000s\")"));
+    assert!(message("tomorrow-ish").contains("`tomorrow-ish` into a time (in \"tomorrow-ish\")"));
+    assert!(parse_duration("1h 30x")
+        .unwrap_err()
+        .to_string()
+        .ends_with("(in \"1h 30x\")"));
+    assert!(parse_time_tz("today + 1h", Utc)
+        .unwrap_err()
+        .to_string()
+        .ends_with("(in \"today + 1h\")"));
+
+    // errors about the timezone rather than the input are unchanged
+    assert_eq!(
+        Parser::new()
+            .resolve_ambiguous(AmbiguousStrategy::Reject)
+            .parse_tz("2018-11-04 01:30", Eastern)
+            .unwrap_err()
+            .to_string(),
+        "ambiguous timestamp in the given timezone: 2018-11-04T01:30:00-04:00 or 2018-11-04T01:30:00-05:00"
+    );
+}
+
+/// Test an error for a time skipped by the timezone reports the local time.
+#[test]
+fn error_never() {
+    let err = Parser::new()
+        .parse_tz("2018-03-25 00:15:30", GapZone)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::Never(Some(naive(2018, 3, 25, 0, 15) + Duration::seconds(30)))
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid timestamp in the given timezone: the local time 2018-03-25 00:15:30 is skipped by the timezone \
+         (e.g. a daylight saving time transition)"
+    );
+    assert_eq!(
+        Error::Never(None).to_string(),
+        "invalid timestamp in the given timezone"
+    );
+}
+
+#[test]
+fn error_eq() {
+    let err = parse_timestamp_tz("+5", Utc).unwrap_err();
+    assert_eq!(err, parse_timestamp_tz("+5", Utc).unwrap_err());
+    assert_eq!(
+        err.clone(),
+        Error::TimeUnit {
+            unit: String::new(),
+            at: 2,
+            input: "+5".to_owned()
+        }
+    );
+    assert_ne!(err, parse_timestamp_tz("+5 bad", Utc).unwrap_err());
+}
+
+#[test]
+fn error_source() {
+    use std::error::Error as _;
+
+    let err = parse_timestamp_tz("today + 5s ~3s", Utc).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Number {
+            source: Some(_),
+            ..
+        }
     ));
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "cannot parse integer from empty string"
+    );
+
+    let err = parse_timestamp_tz("+1.5.5h", Utc).unwrap_err();
+    assert!(matches!(err, Error::Number { source: None, .. }));
+    assert!(err.source().is_none());
 }
 
 #[test]
@@ -725,44 +4492,67 @@ fn invalid_timeunit() {
     // missing time unit
     assert!(matches!(
         parse_timestamp_tz("+5", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("5 ago", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("today +5", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("today -5s 6", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     // unknown time unit
     assert!(matches!(
         parse_timestamp_tz("+5 bad", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("5 bad ago", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("today +5 bad", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
 
     assert!(matches!(
         parse_timestamp_tz("today -5s 6 bad", Utc),
-        Err(Error::TimeUnit(_))
+        Err(Error::TimeUnit { .. })
     ));
+
+    // an uppercase form of a time unit explains that time units are case-sensitive
+    assert_eq!(
+        parse_timestamp_tz("+1S", Utc).unwrap_err().to_string(),
+        "invalid time unit at byte 2: S (in \"+1S\"); time units are case-sensitive, so `S` must be written `s` \
+         (only months are uppercase, as `M`, since `m` is minutes)"
+    );
+    assert!(parse_timestamp_tz("today + 2H", Utc)
+        .unwrap_err()
+        .to_string()
+        .ends_with(
+            "so `H` must be written `h` (only months are uppercase, as `M`, since `m` is minutes)"
+        ));
+    assert!(parse_timestamp_tz("+1Min", Utc)
+        .unwrap_err()
+        .to_string()
+        .contains("`Min` must be written `min`"));
+    // an unknown time unit, or an uppercase ISO 8601 duration, is unaffected
+    assert!(!parse_timestamp_tz("+5 Bad", Utc)
+        .unwrap_err()
+        .to_string()
+        .contains("case-sensitive"));
+    assert!(parse_timestamp_tz("today + PT1S", Utc).is_ok());
 }
 
 fn parse_timestamp_tz_aux<Tz: TimeZone>(timestamp: &str, timezone: Tz) -> DateTime<Tz> {
@@ -773,6 +4563,90 @@ fn parse_timestamp_tz_aux<Tz: TimeZone>(timestamp: &str, timezone: Tz) -> DateTi
 }
 
 fn today_time<Tz: TimeZone>(tz: &Tz, t: Option<NaiveTime>) -> DateTime<Tz> {
-    let t = naive_today(tz).and_time(t.unwrap_or_default());
+    let t = naive_today(tz, &Utc::now()).and_time(t.unwrap_or_default());
     tz.from_local_datetime(&t).unwrap()
 }
+
+/// Run a function, counting the heap allocations it makes on the current thread.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let res = f();
+    (res, ALLOCATIONS.with(Cell::get) - before)
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The system allocator, counting the allocations of each thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // the counter is unavailable while a thread is torn down, when its allocations are not counted
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn naive(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, min, 0)
+        .unwrap()
+}
+
+/// A timezone which springs forward from UTC to UTC+1 at 2018-03-25 00:00 UTC,
+/// skipping the first hour of the local date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GapZone;
+
+impl GapZone {
+    fn transition() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2018, 3, 25)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+}
+
+impl TimeZone for GapZone {
+    type Offset = FixedOffset;
+
+    fn from_offset(_: &FixedOffset) -> Self {
+        GapZone
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+        self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        let transition = Self::transition();
+        if *local < transition {
+            LocalResult::Single(FixedOffset::east_opt(0).unwrap())
+        } else if *local >= transition + Duration::hours(1) {
+            LocalResult::Single(FixedOffset::east_opt(3600).unwrap())
+        } else {
+            LocalResult::None
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+        self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match *utc < Self::transition() {
+            true => FixedOffset::east_opt(0).unwrap(),
+            false => FixedOffset::east_opt(3600).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use chrono::{DateTime, Utc};
+
+use crate::parser::KEYWORDS;
+use crate::{Error, Parser};
+
+/// A token of a timestamp, along with the byte range it spans in the timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// A word which is a time on its own or frames an offset, such as `now`, `Mon`, `UTC`, `in` or `ago`,
+    /// or the `@` of a time relative to the epoch.
+    Keyword(Range<usize>),
+    /// A date or time of day, such as `2018-08-20` or `09:11:12.5`.
+    DatePart(Range<usize>),
+    /// A `+` or `-` adding or subtracting the time span following it.
+    Sign(Range<usize>),
+    /// The number of a time span, such as the `1.5` of `1.5h`.
+    Number(Range<usize>),
+    /// The time unit of a time span, such as the `h` of `1.5h`.
+    Unit(Range<usize>),
+    /// A run of whitespace.
+    Whitespace(Range<usize>),
+}
+
+impl Token {
+    /// The byte range of the token in the timestamp.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Token::Keyword(span)
+            | Token::DatePart(span)
+            | Token::Sign(span)
+            | Token::Number(span)
+            | Token::Unit(span)
+            | Token::Whitespace(span) => span.clone(),
+        }
+    }
+}
+
+/// The words framing an offset relative to now, which are not time units.
+const OFFSET_KEYWORDS: [&str; 5] = ["in", "left", "ago", "from", "now"];
+
+/// The kind of a token, before the range of its run is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Keyword,
+    DatePart,
+    Sign,
+    Number,
+    Unit,
+    Whitespace,
+}
+
+impl Kind {
+    fn token(self, span: Range<usize>) -> Token {
+        match self {
+            Kind::Keyword => Token::Keyword(span),
+            Kind::DatePart => Token::DatePart(span),
+            Kind::Sign => Token::Sign(span),
+            Kind::Number => Token::Number(span),
+            Kind::Unit => Token::Unit(span),
+            Kind::Whitespace => Token::Whitespace(span),
+        }
+    }
+}
+
+/// Split a timestamp into its tokens, as parsed with the default options of a `Parser`.
+///
+/// * `ts` - a str of a timestamp with whitespace intact.
+pub(crate) fn tokenize(ts: &str) -> Result<Vec<Token>, Error> {
+    let parser = Parser::new();
+    // the structure of a timestamp is only known once it parses, so any error parsing it is reported instead
+    parser.parse_tz_at(ts, Utc, DateTime::UNIX_EPOCH)?;
+
+    let mut tokens = Vec::new();
+    if parser.is_offset_from_now(ts) {
+        lex_offsets(ts, 0, &mut tokens);
+    } else if let Some(span) = ts.strip_prefix('@') {
+        tokens.push(Token::Keyword(0..1));
+        lex_offsets(span, 1, &mut tokens);
+    } else {
+        let (split, _) = parser
+            .parse_general(ts, &Utc, &DateTime::UNIX_EPOCH)
+            .map_err(|e| e.with_input(ts))?;
+        let p = split.unwrap_or(ts.len());
+        lex_time(&ts[..p], &mut tokens);
+        lex_offsets(&ts[p..], p, &mut tokens);
+    }
+    Ok(tokens)
+}
+
+/// Split a time into keywords and date parts, separated by whitespace.
+fn lex_time(ts: &str, tokens: &mut Vec<Token>) {
+    let kind_of = |c: char| match c.is_whitespace() {
+        true => Kind::Whitespace,
+        false => Kind::DatePart,
+    };
+    let is_keyword = |word: &str| word.chars().all(char::is_alphabetic) || KEYWORDS.contains(&word);
+    lex(ts, 0, kind_of, is_keyword, tokens);
+}
+
+/// Split offsets into signs, numbers, time units and the keywords framing an offset relative to now.
+///
+/// * `start` - the byte position of the offsets in the timestamp.
+fn lex_offsets(ts: &str, start: usize, tokens: &mut Vec<Token>) {
+    let kind_of = |c: char| match c {
+        '+' | '-' => Kind::Sign,
+        '0'..='9' | '.' => Kind::Number,
+        c if c.is_whitespace() => Kind::Whitespace,
+        _ => Kind::Unit,
+    };
+    let is_keyword = |word: &str| OFFSET_KEYWORDS.contains(&word);
+    lex(ts, start, kind_of, is_keyword, tokens);
+}
+
+/// Split a str into runs of characters of the same kind, each of which is a token,
+/// except a sign which is always a token on its own.
+///
+/// * `start` - the byte position of the str in the timestamp.
+/// * `kind_of` - the kind of the token a character belongs to.
+/// * `is_keyword` - whether a run of a date part or time unit is instead a keyword.
+fn lex(
+    ts: &str,
+    start: usize,
+    kind_of: impl Fn(char) -> Kind,
+    is_keyword: impl Fn(&str) -> bool,
+    tokens: &mut Vec<Token>,
+) {
+    let mut push = |kind: Kind, run: Range<usize>| {
+        let kind = match kind {
+            Kind::DatePart | Kind::Unit if is_keyword(&ts[run.clone()]) => Kind::Keyword,
+            kind => kind,
+        };
+        tokens.push(kind.token((start + run.start)..(start + run.end)));
+    };
+
+    let mut run: Option<(Kind, usize)> = None;
+    for (i, c) in ts.char_indices() {
+        let kind = kind_of(c);
+        match run {
+            Some((run_kind, _)) if run_kind == kind && kind != Kind::Sign => {}
+            Some((run_kind, run_start)) => {
+                push(run_kind, run_start..i);
+                run = Some((kind, i));
+            }
+            None => run = Some((kind, i)),
+        }
+    }
+    if let Some((run_kind, run_start)) = run {
+        push(run_kind, run_start..ts.len());
+    }
+}